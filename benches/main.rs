@@ -10,15 +10,13 @@ use unlisp::object::LispObject;
 use unlisp::reader::Reader;
 
 fn read(s: impl Into<String>) -> LispObject {
-    let s = s.into();
-    let mut bytes = s.as_bytes();
-    let mut reader = Reader::create(&mut bytes);
+    let mut reader = Reader::create(std::io::Cursor::new(s.into()));
     reader.read_form().unwrap().unwrap()
 }
 
 fn env() -> Env {
     let mut env = Env::new();
-    init_env(&mut env);
+    init_env(&mut env).unwrap();
 
     env
 }
@@ -0,0 +1,380 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Arbitrary-precision integer. Magnitude is stored as little-endian
+/// base 2^32 digits without leading zeros, zero has no digits and is
+/// never negative, so derived equality and hashing are structural.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct BigInt {
+    negative: bool,
+    digits: Vec<u32>,
+}
+
+fn trim(digits: &mut Vec<u32>) {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut res = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+
+    for (i, d) in long.iter().enumerate() {
+        let sum = u64::from(*d) + u64::from(*short.get(i).unwrap_or(&0)) + carry;
+        res.push(sum as u32);
+        carry = sum >> 32;
+    }
+
+    if carry != 0 {
+        res.push(carry as u32);
+    }
+
+    res
+}
+
+// requires a >= b
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut res = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+
+    for (i, d) in a.iter().enumerate() {
+        let mut diff = i64::from(*d) - i64::from(*b.get(i).unwrap_or(&0)) - borrow;
+        if diff < 0 {
+            diff += 1 << 32;
+            borrow = 1;
+        } else {
+            borrow = 0;
+        }
+        res.push(diff as u32);
+    }
+
+    trim(&mut res);
+    res
+}
+
+fn mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut res = vec![0u32; a.len() + b.len()];
+
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate() {
+            let cur = u64::from(res[i + j]) + u64::from(*x) * u64::from(*y) + carry;
+            res[i + j] = cur as u32;
+            carry = cur >> 32;
+        }
+        res[i + b.len()] = carry as u32;
+    }
+
+    trim(&mut res);
+    res
+}
+
+// multiplies by a small factor and adds a small term in place
+fn mul_add_small(a: &mut Vec<u32>, factor: u32, term: u32) {
+    let mut carry = u64::from(term);
+
+    for d in a.iter_mut() {
+        let cur = u64::from(*d) * u64::from(factor) + carry;
+        *d = cur as u32;
+        carry = cur >> 32;
+    }
+
+    if carry != 0 {
+        a.push(carry as u32);
+    }
+}
+
+// divides by a small divisor in place, returns remainder
+fn div_small(a: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut rem = 0u64;
+
+    for d in a.iter_mut().rev() {
+        let cur = (rem << 32) | u64::from(*d);
+        *d = (cur / u64::from(divisor)) as u32;
+        rem = cur % u64::from(divisor);
+    }
+
+    trim(a);
+    rem as u32
+}
+
+fn shl1_or(a: &mut Vec<u32>, bit: u32) {
+    let mut carry = bit;
+
+    for d in a.iter_mut() {
+        let next_carry = *d >> 31;
+        *d = (*d << 1) | carry;
+        carry = next_carry;
+    }
+
+    if carry != 0 {
+        a.push(carry);
+    }
+}
+
+// binary long division, requires non-zero divisor
+fn divmod_mag(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if b.len() == 1 {
+        let mut quot = a.to_vec();
+        let rem = div_small(&mut quot, b[0]);
+        let mut rem = vec![rem];
+        trim(&mut rem);
+        return (quot, rem);
+    }
+
+    let mut quot = vec![0u32; a.len()];
+    let mut rem = vec![];
+
+    for i in (0..a.len() * 32).rev() {
+        shl1_or(&mut rem, (a[i / 32] >> (i % 32)) & 1);
+        if cmp_mag(&rem, b) != Ordering::Less {
+            rem = sub_mag(&rem, b);
+            quot[i / 32] |= 1 << (i % 32);
+        }
+    }
+
+    trim(&mut quot);
+    (quot, rem)
+}
+
+impl BigInt {
+    fn from_parts(negative: bool, digits: Vec<u32>) -> Self {
+        let mut digits = digits;
+        trim(&mut digits);
+        let negative = negative && !digits.is_empty();
+
+        Self { negative, digits }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.digits.len() > 2 {
+            return None;
+        }
+
+        let mag = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0u64, |acc, d| (acc << 32) | u64::from(*d));
+
+        if self.negative {
+            if mag <= i64::MIN.unsigned_abs() {
+                Some((mag as i64).wrapping_neg())
+            } else {
+                None
+            }
+        } else if mag <= i64::MAX as u64 {
+            Some(mag as i64)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let mag = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0f64, |acc, d| acc * 4_294_967_296f64 + f64::from(*d));
+
+        if self.negative {
+            -mag
+        } else {
+            mag
+        }
+    }
+
+    pub fn neg(&self) -> Self {
+        Self::from_parts(!self.negative, self.digits.clone())
+    }
+
+    pub fn abs(&self) -> Self {
+        Self::from_parts(false, self.digits.clone())
+    }
+
+    pub fn add(&self, rhs: &Self) -> Self {
+        if self.negative == rhs.negative {
+            return Self::from_parts(self.negative, add_mag(&self.digits, &rhs.digits));
+        }
+
+        match cmp_mag(&self.digits, &rhs.digits) {
+            Ordering::Less => Self::from_parts(rhs.negative, sub_mag(&rhs.digits, &self.digits)),
+            _ => Self::from_parts(self.negative, sub_mag(&self.digits, &rhs.digits)),
+        }
+    }
+
+    pub fn sub(&self, rhs: &Self) -> Self {
+        self.add(&rhs.neg())
+    }
+
+    pub fn mul(&self, rhs: &Self) -> Self {
+        Self::from_parts(
+            self.negative != rhs.negative,
+            mul_mag(&self.digits, &rhs.digits),
+        )
+    }
+
+    /// Division truncated towards zero, remainder has the sign of
+    /// dividend. Returns `None` if divisor is zero.
+    pub fn divmod(&self, rhs: &Self) -> Option<(Self, Self)> {
+        if rhs.is_zero() {
+            return None;
+        }
+
+        let (quot, rem) = divmod_mag(&self.digits, &rhs.digits);
+        Some((
+            Self::from_parts(self.negative != rhs.negative, quot),
+            Self::from_parts(self.negative, rem),
+        ))
+    }
+
+    /// Parses optionally signed digits in given radix (2 to 36).
+    pub fn parse_radix(s: &str, radix: u32) -> Option<Self> {
+        let (negative, digits) = match s.chars().next() {
+            Some('-') => (true, &s[1..]),
+            Some('+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        if digits.is_empty() {
+            return None;
+        }
+
+        let mut mag = vec![];
+        for c in digits.chars() {
+            mul_add_small(&mut mag, radix, c.to_digit(radix)?);
+        }
+
+        Some(Self::from_parts(negative, mag))
+    }
+
+    pub fn to_string_radix(&self, radix: u32) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+
+        let mut mag = self.digits.clone();
+        let mut chars = vec![];
+        while !mag.is_empty() {
+            let d = div_small(&mut mag, radix);
+            chars.push(std::char::from_digit(d, radix).unwrap());
+        }
+
+        if self.negative {
+            chars.push('-');
+        }
+
+        chars.into_iter().rev().collect()
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(i: i64) -> Self {
+        let mag = i.unsigned_abs();
+        Self::from_parts(i < 0, vec![mag as u32, (mag >> 32) as u32])
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, rhs: &Self) -> Ordering {
+        match (self.negative, rhs.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_mag(&self.digits, &rhs.digits),
+            (true, true) => cmp_mag(&rhs.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
+        Some(self.cmp(rhs))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_string_radix(10))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigInt {
+        BigInt::parse_radix(s, 10).unwrap()
+    }
+
+    #[test]
+    fn test_i64_roundtrip() {
+        for i in &[0, 1, -1, 42, 1 << 40, i64::MAX, i64::MIN] {
+            assert_eq!(BigInt::from(*i).to_i64(), Some(*i));
+            assert_eq!(BigInt::from(*i).to_string(), i.to_string());
+        }
+
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+        assert_eq!(big("-9223372036854775809").to_i64(), None);
+        assert_eq!(big("-0"), BigInt::from(0));
+    }
+
+    #[test]
+    fn test_parse_print() {
+        let s = "123456789012345678901234567890";
+        assert_eq!(big(s).to_string(), s);
+        assert_eq!(big(&format!("-{}", s)).to_string(), format!("-{}", s));
+        assert_eq!(BigInt::parse_radix("ff", 16).unwrap(), BigInt::from(255));
+        assert_eq!(BigInt::from(-255).to_string_radix(16), "-ff");
+        assert_eq!(BigInt::parse_radix("12a", 10), None);
+        assert_eq!(BigInt::parse_radix("-", 10), None);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = big("99999999999999999999");
+        let b = big("12345678901234567890");
+
+        assert_eq!(a.add(&b), big("112345678901234567889"));
+        assert_eq!(a.sub(&b), big("87654321098765432109"));
+        assert_eq!(b.sub(&a), big("-87654321098765432109"));
+        assert_eq!(a.add(&a.neg()), BigInt::from(0));
+        assert_eq!(a.mul(&b), big("1234567890123456788987654321098765432110"));
+        assert!(a.mul(&b.neg()).is_negative());
+
+        let (q, r) = a.divmod(&b).unwrap();
+        assert_eq!((q, r), (BigInt::from(8), big("1234568790123456879")));
+
+        let (q, r) = a.neg().divmod(&b).unwrap();
+        assert_eq!((q, r), (BigInt::from(-8), big("-1234568790123456879")));
+
+        let (q, r) = a.divmod(&BigInt::from(7)).unwrap();
+        assert_eq!((q, r), (big("14285714285714285714"), BigInt::from(1)));
+
+        assert_eq!(a.divmod(&BigInt::from(0)), None);
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(big("-100000000000000000000") < BigInt::from(-1));
+        assert!(BigInt::from(-1) < BigInt::from(0));
+        assert!(big("100000000000000000000") > big("99999999999999999999"));
+        assert_eq!(BigInt::from(1).to_f64(), 1.0);
+        assert_eq!(big("-4294967296").to_f64(), -4294967296.0);
+    }
+}
@@ -7,36 +7,52 @@ use std::thread;
 
 use unlisp::common::*;
 use unlisp::env;
+use unlisp::error::ErrorWithStackTrace;
 use unlisp::print::print_stack_trace;
 use unlisp::reader;
 
 fn repl() {
-    let mut stdin = io::stdin();
-
     let prompt = || {
         print!(">>> ");
         io::stdout().flush().unwrap();
     };
 
     let mut env = env::Env::new();
-    init_env(&mut env);
+    if let Err(e) = init_env(&mut env) {
+        println!("error during stdlib eval: {}", e);
+        if let Some(e) = e.downcast_ref::<ErrorWithStackTrace>() {
+            print_stack_trace(&e.stack_trace);
+        }
+        return;
+    }
 
-    let mut reader = reader::Reader::create(&mut stdin);
+    let mut reader = reader::Reader::create_with_env(io::stdin(), "<stdin>", env.clone());
 
     prompt();
     loop {
         match reader.read_form() {
-            Ok(Some(form)) => match macroexpand_and_eval(env.clone(), &form) {
-                Ok(lo) => {
-                    println!("{}", lo);
+            Ok(Some(form)) => {
+                let mut env = env.clone();
+                env.set_location(reader.form_location());
+                match macroexpand_and_eval(env, &form) {
+                    Ok(lo) => {
+                        println!("{}", lo);
+                    }
+                    Err(e) => {
+                        println!("error: {}", e);
+                        print_stack_trace(&e.stack_trace);
+                    }
                 }
-                Err(e) => {
-                    println!("error: {}", e.err);
-                    print_stack_trace(&e.stack_trace);
-                }
-            },
+            }
             Ok(None) => break,
-            Err(ref e) => println!("reader error: {}", e),
+            Err(ref e) => {
+                println!("reader error: {}", e);
+                // resume from the next line, so that rest of malformed
+                // form is not read as new forms
+                if reader.skip_rest_of_line().is_err() {
+                    break;
+                }
+            }
         }
         prompt();
     }
@@ -44,7 +60,7 @@ fn repl() {
 
 fn main() {
     let child = thread::Builder::new()
-        .stack_size(10 * 1024 * 1024 * 1024)
+        .stack_size(512 * 1024 * 1024)
         .spawn(repl)
         .unwrap();
 
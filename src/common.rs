@@ -3,10 +3,10 @@ use crate::eval;
 use crate::macroexpand;
 use crate::native;
 use crate::object;
-use crate::print;
 use crate::reader;
 use crate::special;
 
+use std::error::Error;
 use std::fs;
 
 pub fn macroexpand_and_eval(env: env::Env, form: &object::LispObject) -> eval::EvalResult {
@@ -14,29 +14,22 @@ pub fn macroexpand_and_eval(env: env::Env, form: &object::LispObject) -> eval::E
     eval::eval(env, &expanded)
 }
 
-pub fn eval_stdlib(env: &env::Env) {
-    let mut file = fs::File::open("src/stdlib.unl").expect("stdlib file not found");
+/// Evaluates stdlib forms, stops at the first reader or eval error.
+pub fn eval_stdlib(env: &env::Env) -> Result<(), Box<dyn Error>> {
+    let file = fs::File::open("src/stdlib.unl")?;
+    let mut reader = reader::Reader::create_with_env(file, "src/stdlib.unl", env.clone());
 
-    let mut reader = reader::Reader::create(&mut file);
-    loop {
-        match reader.read_form() {
-            Ok(Some(form)) => {
-                let res = macroexpand_and_eval(env.clone(), &form);
-                res.map_err(|e| {
-                    println!("error during stdlib eval: {}", e.err);
-                    print::print_stack_trace(&e.stack_trace);
-                })
-                .unwrap();
-            }
-            Ok(None) => break,
-
-            Err(ref e) => panic!("Reader error during stdlib eval: {}", e),
-        }
+    while let Some(form) = reader.read_form()? {
+        let mut env = env.clone();
+        env.set_location(reader.form_location());
+        macroexpand_and_eval(env, &form)?;
     }
+
+    Ok(())
 }
 
-pub fn init_env(env: &mut env::Env) {
+pub fn init_env(env: &mut env::Env) -> Result<(), Box<dyn Error>> {
     special::prepare_specials(env);
     native::prepare_natives(env);
-    eval_stdlib(env);
+    eval_stdlib(env)
 }
@@ -3,6 +3,7 @@ use std::hash::Hash;
 use std::hash::Hasher;
 use std::iter::FromIterator;
 use std::rc::Rc;
+use std::rc::Weak;
 
 pub struct List<T> {
     head: Link<T>,
@@ -52,6 +53,18 @@ impl<T> List<T> {
         self.len() == 0
     }
 
+    /// Identity of the first cons cell, shared by clones of the list.
+    pub fn cell_id(&self) -> Option<usize> {
+        self.head.as_ref().map(|cons_rc| Rc::as_ptr(cons_rc) as usize)
+    }
+
+    /// Weak reference to the first cons cell, `None` for empty list.
+    pub fn downgrade(&self) -> Option<WeakList<T>> {
+        self.head
+            .as_ref()
+            .map(|cons_rc| WeakList(Rc::downgrade(cons_rc)))
+    }
+
     pub fn cons_rc(&self, x: Rc<T>) -> Self {
         List {
             head: Some(Rc::new(Cons {
@@ -114,6 +127,22 @@ impl<T> List<T> {
     }
 }
 
+/// Weak reference to a list which doesn't keep its elements alive, but
+/// keeps identity of its first cell from being reused by other lists.
+pub struct WeakList<T>(Weak<Cons<T>>);
+
+impl<T> WeakList<T> {
+    pub fn is_alive(&self) -> bool {
+        self.0.strong_count() > 0
+    }
+}
+
+impl<T> fmt::Debug for WeakList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WeakList(0x{:x})", self.0.as_ptr() as usize)
+    }
+}
+
 impl<T> Clone for List<T> {
     fn clone(&self) -> Self {
         List {
@@ -1,7 +1,10 @@
 use crate::cons::List;
 use crate::error::ErrorWithStackTrace;
+use crate::location::SourceLocation;
+use crate::location::SourceMap;
 use crate::object::*;
 use im::HashMap;
+use im::HashSet;
 use std::cell::RefCell;
 use std::error::Error;
 use std::ops::Deref;
@@ -15,11 +18,32 @@ pub enum StackFrameDesignator {
     Top,
 }
 
-pub type StackTrace = List<StackFrameDesignator>;
+#[derive(Debug, Clone)]
+pub struct StackFrame {
+    pub designator: StackFrameDesignator,
+    // location of the call
+    pub location: Option<SourceLocation>,
+}
+
+impl StackFrame {
+    fn top() -> Self {
+        Self {
+            designator: StackFrameDesignator::Top,
+            location: None,
+        }
+    }
+}
+
+pub type StackTrace = List<StackFrame>;
+
+/// Previous global values of dynamically rebound special variables,
+/// `None` meaning that variable was unbound
+pub type SavedValues = Vec<(Symbol, Option<LispObject>)>;
 
 #[derive(Debug, Clone)]
-struct LocalEnv {
-    sym_env: HashMap<Symbol, LispObject>,
+pub struct LocalEnv {
+    // bindings are shared cells, so closures see assignments
+    sym_env: HashMap<Symbol, Rc<RefCell<LispObject>>>,
     fn_env: HashMap<Symbol, Function>,
     macro_env: HashMap<Symbol, Function>,
     stack_trace: StackTrace,
@@ -31,7 +55,7 @@ impl LocalEnv {
             sym_env: HashMap::new(),
             fn_env: HashMap::new(),
             macro_env: HashMap::new(),
-            stack_trace: List::empty().cons(StackFrameDesignator::Top),
+            stack_trace: List::empty().cons(StackFrame::top()),
         }
     }
 }
@@ -42,6 +66,9 @@ struct GlobalEnv {
     fn_env: HashMap<Symbol, Function>,
     macro_env: HashMap<Symbol, Function>,
     special_env: HashMap<Symbol, NativeFnWrapper>,
+    special_vars: HashSet<Symbol>,
+    // functions called by reader for `#` followed by the key char
+    dispatch_macros: HashMap<char, Function>,
 }
 
 impl GlobalEnv {
@@ -51,6 +78,8 @@ impl GlobalEnv {
             fn_env: HashMap::new(),
             special_env: HashMap::new(),
             macro_env: HashMap::new(),
+            special_vars: HashSet::new(),
+            dispatch_macros: HashMap::new(),
         }
     }
 }
@@ -59,6 +88,13 @@ impl GlobalEnv {
 pub struct Env {
     global_env: Rc<RefCell<GlobalEnv>>,
     local_env: LocalEnv,
+    // whether top stack frame can be replaced by a call in tail position
+    tail_frame: bool,
+    source_map: SourceMap,
+    // location of the innermost form being evaluated, if known
+    location: Option<SourceLocation>,
+    // innermost form with known location being evaluated
+    form: Option<List<LispObject>>,
 }
 
 macro_rules! lookup_symbol {
@@ -77,6 +113,10 @@ impl Env {
         Self {
             global_env: Rc::new(RefCell::new(GlobalEnv::new())),
             local_env: LocalEnv::new(),
+            tail_frame: false,
+            source_map: SourceMap::new(),
+            location: None,
+            form: None,
         }
     }
 
@@ -84,6 +124,30 @@ impl Env {
         Self {
             global_env: Rc::new(RefCell::new(self.global_env().clone())),
             local_env: self.local_env.clone(),
+            tail_frame: self.tail_frame,
+            source_map: self.source_map.clone(),
+            location: self.location.clone(),
+            form: self.form.clone(),
+        }
+    }
+
+    pub fn capture_local_env(&self) -> LocalEnv {
+        let mut captured = self.local_env.clone();
+        captured.stack_trace = List::empty();
+        captured
+    }
+
+    pub fn with_captured_local_env(&self, captured: &LocalEnv) -> Self {
+        let mut local_env = captured.clone();
+        local_env.stack_trace = self.local_env.stack_trace.clone();
+
+        Self {
+            global_env: self.global_env.clone(),
+            local_env,
+            tail_frame: false,
+            source_map: self.source_map.clone(),
+            location: self.location.clone(),
+            form: self.form.clone(),
         }
     }
 
@@ -100,7 +164,14 @@ impl Env {
     }
 
     pub fn lookup_symbol_value(&self, s: &Symbol) -> Option<LispObject> {
-        lookup_symbol!(self, sym_env, s)
+        match self.local_env.sym_env.get(s) {
+            Some(cell) => Some(cell.borrow().clone()),
+            None => self.global_env().sym_env.get(s).cloned(),
+        }
+    }
+
+    pub fn lookup_global_value(&self, s: &Symbol) -> Option<LispObject> {
+        self.global_env().sym_env.get(s).cloned()
     }
 
     pub fn lookup_symbol_function(&self, s: &Symbol) -> Option<Function> {
@@ -111,8 +182,62 @@ impl Env {
         lookup_symbol!(self, macro_env, s)
     }
 
+    pub fn lookup_dispatch_macro(&self, c: char) -> Option<Function> {
+        self.global_env().dispatch_macros.get(&c).cloned()
+    }
+
     pub fn set_local_value(&mut self, s: Symbol, val: LispObject) {
-        self.local_env.sym_env.insert(s, val);
+        self.local_env.sym_env.insert(s, Rc::new(RefCell::new(val)));
+    }
+
+    pub fn set_global_value(&mut self, s: Symbol, val: LispObject) {
+        self.global_env_mut().sym_env.insert(s, val);
+    }
+
+    pub fn declare_special_var(&mut self, s: Symbol) {
+        self.global_env_mut().special_vars.insert(s);
+    }
+
+    pub fn is_special_var(&self, s: &Symbol) -> bool {
+        self.global_env().special_vars.contains(s)
+    }
+
+    // binds special variables dynamically, saving their previous values,
+    // and all other variables lexically
+    pub fn bind_value(&mut self, s: Symbol, val: LispObject, saved: &mut SavedValues) {
+        if self.is_special_var(&s) {
+            let old = self.global_env_mut().sym_env.insert(s.clone(), val);
+            saved.push((s, old));
+        } else {
+            self.set_local_value(s, val);
+        }
+    }
+
+    pub fn restore_values(&mut self, saved: SavedValues) {
+        let mut global = self.global_env_mut();
+        for (s, old) in saved.into_iter().rev() {
+            match old {
+                Some(val) => global.sym_env.insert(s, val),
+                None => global.sym_env.remove(&s),
+            };
+        }
+    }
+
+    // assigns to the innermost existing binding, returns false if symbol is unbound
+    pub fn assign_value(&mut self, s: &Symbol, val: LispObject) -> bool {
+        if let Some(cell) = self.local_env.sym_env.get(s) {
+            *cell.borrow_mut() = val;
+            return true;
+        }
+
+        let mut global = self.global_env_mut();
+        match global.sym_env.get_mut(s) {
+            Some(global_val) => {
+                *global_val = val;
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn set_global_function(&mut self, s: Symbol, val: Function) {
@@ -123,25 +248,85 @@ impl Env {
         self.global_env_mut().macro_env.insert(s, val);
     }
 
+    pub fn set_dispatch_macro(&mut self, c: char, val: Function) {
+        self.global_env_mut().dispatch_macros.insert(c, val);
+    }
+
     pub fn set_global_special(&mut self, s: Symbol, val: NativeFnWrapper) {
         self.global_env_mut().special_env.insert(s, val);
     }
 
-    pub fn push_stack_frame_name(&mut self, name: Symbol) {
+    pub fn mark_tail_frame(&mut self) {
+        self.tail_frame = true;
+    }
+
+    pub fn unmark_tail_frame(&mut self) {
+        self.tail_frame = false;
+    }
+
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+
+    /// Makes location of form, if it's known, the current one.
+    pub fn track_location(&mut self, form: &List<LispObject>) {
+        if let Some(location) = self.source_map.get(form) {
+            self.location = Some(location);
+            self.form = Some(form.clone());
+        }
+    }
+
+    /// Makes location of `sym` in the current form, if it's known, the
+    /// current one.
+    pub fn track_symbol_location(&mut self, sym: &Symbol) {
+        let location = self
+            .form
+            .as_ref()
+            .and_then(|form| self.source_map.find_symbol(form, sym));
+        if location.is_some() {
+            self.location = location;
+        }
+    }
+
+    /// Makes `location` the current one, e.g. for top level forms which
+    /// are not lists.
+    pub fn set_location(&mut self, location: Option<SourceLocation>) {
+        self.location = location;
+    }
+
+    fn push_stack_frame(&mut self, designator: StackFrameDesignator) {
+        let frame = StackFrame {
+            designator,
+            location: self.location.clone(),
+        };
+
         let cur_stack_trace = &self.local_env.stack_trace;
-        self.local_env.stack_trace = cur_stack_trace.cons(StackFrameDesignator::Name(name));
+        let cur_stack_trace = if self.tail_frame {
+            cur_stack_trace.tail()
+        } else {
+            cur_stack_trace.clone()
+        };
+
+        self.local_env.stack_trace = cur_stack_trace.cons(frame);
+        self.tail_frame = false;
+    }
+
+    pub fn push_stack_frame_name(&mut self, name: Symbol) {
+        self.push_stack_frame(StackFrameDesignator::Name(name));
     }
 
     pub fn push_stack_frame_sig(&mut self, sig: FunctionSignature) {
-        let cur_stack_trace = &self.local_env.stack_trace;
-        self.local_env.stack_trace = cur_stack_trace.cons(StackFrameDesignator::Signature(sig));
+        self.push_stack_frame(StackFrameDesignator::Signature(sig));
     }
 
     pub fn get_stack_trace(&self) -> StackTrace {
         self.local_env.stack_trace.clone()
     }
 
-    pub fn attach_st_box<T>(&self, res: Result<T, Box<Error>>) -> Result<T, ErrorWithStackTrace> {
+    pub fn attach_st_box<T>(
+        &self,
+        res: Result<T, Box<dyn Error>>,
+    ) -> Result<T, ErrorWithStackTrace> {
         res.map_err(|e| self.st_box_err(e))
     }
 
@@ -156,7 +341,7 @@ impl Env {
         self.st_box_err(Box::new(err))
     }
 
-    pub fn st_box_err(&self, err: Box<Error>) -> ErrorWithStackTrace {
-        ErrorWithStackTrace::new(err, self.get_stack_trace())
+    pub fn st_box_err(&self, err: Box<dyn Error>) -> ErrorWithStackTrace {
+        ErrorWithStackTrace::new(err, self.get_stack_trace(), self.location.clone())
     }
 }
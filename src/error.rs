@@ -1,24 +1,39 @@
 use crate::env::StackTrace;
+use crate::location::SourceLocation;
 use std::error::Error;
 use std::fmt;
 
-type GenError = Box<Error>;
+type GenError = Box<dyn Error>;
 
 #[derive(Debug)]
 pub struct ErrorWithStackTrace {
     pub err: GenError,
     pub stack_trace: StackTrace,
+    // location of the innermost form being evaluated, if known
+    pub location: Option<SourceLocation>,
 }
 
 impl ErrorWithStackTrace {
-    pub fn new(err: GenError, trace: StackTrace) -> Self {
+    pub fn new(err: GenError, trace: StackTrace, location: Option<SourceLocation>) -> Self {
         Self {
             err: err,
             stack_trace: trace,
+            location,
         }
     }
 }
 
+impl fmt::Display for ErrorWithStackTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match &self.location {
+            Some(location) => write!(f, "{}: {}", location, self.err),
+            None => write!(f, "{}", self.err),
+        }
+    }
+}
+
+impl Error for ErrorWithStackTrace {}
+
 #[derive(Debug, Clone)]
 pub struct CastError {
     from: String,
@@ -80,19 +95,30 @@ impl Error for ArityError {}
 #[derive(Debug, Clone)]
 pub struct SyntaxError {
     message: String,
+    location: Option<SourceLocation>,
 }
 
 impl SyntaxError {
     pub fn new(message: impl Into<String>) -> Self {
         Self {
             message: message.into(),
+            location: None,
         }
     }
+
+    /// Sets location, unless error already has one.
+    pub fn with_location(mut self, location: SourceLocation) -> Self {
+        self.location.get_or_insert(location);
+        self
+    }
 }
 
 impl fmt::Display for SyntaxError {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "{}", self.message)
+        match &self.location {
+            Some(location) => write!(f, "{}: {}", location, self.message),
+            None => write!(f, "{}", self.message),
+        }
     }
 }
 
@@ -146,3 +172,54 @@ impl fmt::Display for GenericError {
 }
 
 impl Error for GenericError {}
+
+#[derive(Debug, Clone)]
+pub struct DivisionByZero;
+
+impl fmt::Display for DivisionByZero {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "division by zero")
+    }
+}
+
+impl Error for DivisionByZero {}
+
+/// Input ended before a form was read, `incomplete` tells whether it
+/// ended in the middle of one.
+#[derive(Debug, Clone)]
+pub struct EndOfInput {
+    incomplete: bool,
+    // start of the unclosed form or token
+    location: Option<SourceLocation>,
+}
+
+impl EndOfInput {
+    pub fn new(incomplete: bool) -> Self {
+        Self {
+            incomplete,
+            location: None,
+        }
+    }
+
+    /// Sets location, unless error already has one.
+    pub fn with_location(mut self, location: SourceLocation) -> Self {
+        self.location.get_or_insert(location);
+        self
+    }
+}
+
+impl fmt::Display for EndOfInput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        if let Some(location) = &self.location {
+            write!(f, "{}: ", location)?;
+        }
+
+        if self.incomplete {
+            write!(f, "unexpected end of input")
+        } else {
+            write!(f, "end of input")
+        }
+    }
+}
+
+impl Error for EndOfInput {}
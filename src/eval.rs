@@ -1,5 +1,6 @@
 use crate::cons::List;
 use crate::env::Env;
+use crate::env::SavedValues;
 use crate::error;
 use crate::object;
 use crate::object::LispObject;
@@ -7,13 +8,61 @@ use crate::object::Symbol;
 
 pub type EvalResult = Result<LispObject, error::ErrorWithStackTrace>;
 
-pub fn call_function_object(
+/// Outcome of a single evaluation step. Forms in tail position are
+/// not evaluated right away, but handed back to the trampoline in
+/// `eval`, so tail calls do not grow the Rust stack.
+pub enum TailCall {
+    Done(LispObject),
+    Eval(Env, LispObject),
+}
+
+pub type TailCallResult = Result<TailCall, error::ErrorWithStackTrace>;
+
+pub fn trampoline(mut step: TailCall) -> EvalResult {
+    loop {
+        match step {
+            TailCall::Done(res) => return Ok(res),
+            TailCall::Eval(env, form) => step = eval_step(env, &form)?,
+        }
+    }
+}
+
+pub fn eval_body_tail(env: Env, body: &List<LispObject>) -> TailCallResult {
+    if body.is_empty() {
+        return Ok(TailCall::Done(LispObject::nil()));
+    }
+
+    let last = body.len() - 1;
+    for form in body.iter().take(last) {
+        eval(env.clone(), form)?;
+    }
+
+    Ok(TailCall::Eval(env, body.iter().nth(last).unwrap().clone()))
+}
+
+// body is not evaluated in tail position if there are dynamic bindings
+// to undo after it
+pub fn eval_body_restoring(
+    mut env: Env,
+    body: &List<LispObject>,
+    saved: SavedValues,
+) -> TailCallResult {
+    if saved.is_empty() {
+        return eval_body_tail(env, body);
+    }
+
+    let res = eval_body_tail(env.clone(), body).and_then(trampoline);
+    env.restore_values(saved);
+    Ok(TailCall::Done(res?))
+}
+
+pub fn call_function_object_tail(
     mut env: Env,
     function: &object::Function,
     args: List<LispObject>,
     eval_args: bool,
     name_hint: Option<&Symbol>,
-) -> EvalResult {
+) -> TailCallResult {
     let args = if eval_args {
         args.iter()
             .map(|lo| eval(env.clone(), lo))
@@ -24,8 +73,8 @@ pub fn call_function_object(
 
     let has_restarg = function.sig.restarg.is_some();
 
-    if (args.len() < function.sig.arglist.len())
-        || (!has_restarg && function.sig.arglist.len() != args.len())
+    if (args.len() < function.sig.required_count())
+        || (!has_restarg && args.len() > function.sig.arglist.len())
     {
         let expected = function.sig.arglist.len();
         let actual = args.len();
@@ -43,35 +92,47 @@ pub fn call_function_object(
         None => env.push_stack_frame_sig(function.sig.clone()),
     }
 
-    match function.body {
+    match *function.body {
         object::FunctionBody::Native(ref native_body) => native_body.0(env, args),
-        object::FunctionBody::Interpreted(ref interpreted_body) => {
+        object::FunctionBody::Interpreted(ref interpreted_body, ref captured_env) => {
             let mut args = args.iter();
 
-            let mut new_env = env.clone();
+            let mut saved = vec![];
+
+            let mut new_env = env.with_captured_local_env(captured_env);
             for (sym, val) in function.sig.arglist.iter().zip(args.by_ref()) {
-                new_env.set_local_value(sym.clone(), val.clone());
+                new_env.bind_value(sym.clone(), val.clone(), &mut saved);
             }
 
             if has_restarg {
-                let restarg = args.map(|lo| lo.clone()).collect();
-                new_env.set_local_value(
+                let restarg = args.cloned().collect();
+                new_env.bind_value(
                     function.sig.restarg.clone().unwrap(),
                     LispObject::List(restarg),
+                    &mut saved,
                 );
             }
 
-            let mut result = LispObject::nil();
-            for form in interpreted_body.iter() {
-                result = eval(new_env.clone(), form)?;
-            }
-
-            Ok(result)
+            new_env.mark_tail_frame();
+            eval_body_restoring(new_env, interpreted_body, saved)
         }
     }
 }
 
-fn call_symbol(env: Env, form: &LispObject) -> EvalResult {
+pub fn call_function_object(
+    mut env: Env,
+    function: &object::Function,
+    args: List<LispObject>,
+    eval_args: bool,
+    name_hint: Option<&Symbol>,
+) -> EvalResult {
+    env.unmark_tail_frame();
+    trampoline(call_function_object_tail(
+        env, function, args, eval_args, name_hint,
+    )?)
+}
+
+fn call_symbol(env: Env, form: &LispObject) -> TailCallResult {
     let form = env.attach_st(object::to_list(form))?;
     let sym = env.attach_st(object::to_symbol(form.first().unwrap()))?;
     let args = form.tail();
@@ -81,29 +142,49 @@ fn call_symbol(env: Env, form: &LispObject) -> EvalResult {
     if let Some(f) = spec {
         f.0(env, args)
     } else if let Some(ref f) = env.lookup_symbol_function(sym) {
-        call_function_object(env, f, args, true, Some(sym))
+        call_function_object_tail(env, f, args, true, Some(sym))
     } else {
         Err(env.st_err(error::UndefinedSymbol::new(sym.name(), true)))?
     }
 }
 
-pub fn eval(env: Env, form: &LispObject) -> EvalResult {
+fn eval_step(mut env: Env, form: &LispObject) -> TailCallResult {
     match form {
-        self_eval @ LispObject::T => Ok(self_eval.clone()),
-        self_eval @ LispObject::Integer(_) => Ok(self_eval.clone()),
-        self_eval @ LispObject::String(_) => Ok(self_eval.clone()),
-        self_eval @ LispObject::Fn(_) => Ok(self_eval.clone()),
-
-        LispObject::List(ref list) if list.is_empty() => Ok(LispObject::nil()),
-        LispObject::Symbol(s) => {
-            let val = env
-                .lookup_symbol_value(&s)
-                .ok_or_else(|| env.st_err(error::UndefinedSymbol::new(s.name(), false)))?;
-            Ok(val)
+        self_eval @ LispObject::T => Ok(TailCall::Done(self_eval.clone())),
+        self_eval @ LispObject::Integer(_) => Ok(TailCall::Done(self_eval.clone())),
+        self_eval @ LispObject::BigInteger(_) => Ok(TailCall::Done(self_eval.clone())),
+        self_eval @ LispObject::Float(_) => Ok(TailCall::Done(self_eval.clone())),
+        self_eval @ LispObject::String(_) => Ok(TailCall::Done(self_eval.clone())),
+        self_eval @ LispObject::Char(_) => Ok(TailCall::Done(self_eval.clone())),
+        self_eval @ LispObject::Vector(_) => Ok(TailCall::Done(self_eval.clone())),
+        self_eval @ LispObject::Map(_) => Ok(TailCall::Done(self_eval.clone())),
+        self_eval @ LispObject::Set(_) => Ok(TailCall::Done(self_eval.clone())),
+        self_eval @ LispObject::Box(_) => Ok(TailCall::Done(self_eval.clone())),
+        self_eval @ LispObject::Fn(_) => Ok(TailCall::Done(self_eval.clone())),
+        self_eval @ LispObject::Stream(_) => Ok(TailCall::Done(self_eval.clone())),
+
+        LispObject::List(ref list) if list.is_empty() => Ok(TailCall::Done(LispObject::nil())),
+        self_eval @ LispObject::Symbol(s) if s.is_keyword() => {
+            Ok(TailCall::Done(self_eval.clone()))
         }
-        LispObject::List(ref list) => match list.ufirst() {
-            LispObject::Symbol(_) => call_symbol(env, form),
-            _ => Err(env.st_err(error::SyntaxError::new("illegal function call")))?,
+        LispObject::Symbol(s) => match env.lookup_symbol_value(s) {
+            Some(val) => Ok(TailCall::Done(val)),
+            None => {
+                env.track_symbol_location(s);
+                Err(env.st_err(error::UndefinedSymbol::new(s.name(), false)))?
+            }
         },
+        LispObject::List(ref list) => {
+            env.track_location(list);
+            match list.ufirst() {
+                LispObject::Symbol(_) => call_symbol(env, form),
+                _ => Err(env.st_err(error::SyntaxError::new("illegal function call")))?,
+            }
+        }
     }
 }
+
+pub fn eval(mut env: Env, form: &LispObject) -> EvalResult {
+    env.unmark_tail_frame();
+    trampoline(eval_step(env, form)?)
+}
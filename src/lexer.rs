@@ -1,20 +1,55 @@
+use crate::bigint::BigInt;
+use crate::error::EndOfInput;
 use crate::error::SyntaxError;
+use crate::location::SourceLocation;
 use crate::pushback_reader::PushbackReader;
+use crate::stream::Input;
 use std::error::Error;
 use std::io;
 use std::io::Read;
+use std::rc::Rc;
 
-type GenResult<T> = Result<T, Box<Error>>;
+type GenResult<T> = Result<T, Box<dyn Error>>;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     LeftPar,
     RightPar,
+    LeftBracket,
+    RightBracket,
+    LeftBrace,
+    RightBrace,
+    // `#(` which starts a vector closed with `)`
+    HashLeftPar,
+    // `#{` which starts a set closed with `}`
+    HashLeftBrace,
+    Quote,
+    Quasiquote,
+    Unquote,
+    UnquoteSplicing,
     IntegerLiteral(i64),
+    BigIntegerLiteral(BigInt),
+    FloatLiteral(f64),
     StringLiteral(String),
+    CharLiteral(char),
     Symbol(String),
+    // `#;` which comments out the next datum, handled by reader
+    DatumComment,
+    // `#` followed by char without built-in meaning, handled by reader
+    // macro defined for that char
+    DispatchMacro(char),
 }
 
+/// Names of characters that can be read as `#\name` and are printed
+/// back with them.
+pub const CHAR_NAMES: &[(&str, char)] = &[
+    ("space", ' '),
+    ("newline", '\n'),
+    ("tab", '\t'),
+    ("return", '\r'),
+    ("nul", '\0'),
+];
+
 pub fn is_eof<T>(result: &io::Result<T>) -> bool {
     match result {
         Err(e) => e.kind() == io::ErrorKind::UnexpectedEof,
@@ -22,28 +57,194 @@ pub fn is_eof<T>(result: &io::Result<T>) -> bool {
     }
 }
 
-pub struct Lexer<'a, T: Read + 'a> {
-    pbr: PushbackReader<'a, T>,
+// meanings of `#` followed by a char, other chars after `#` are
+// dispatched to reader macros
+#[derive(Debug, Clone, Copy)]
+enum BuiltinDispatch {
+    BlockComment,
+    DatumComment,
+    Vector,
+    Set,
+    Char,
+    Radix,
+}
+
+const BUILTIN_DISPATCH_CHARS: &[(&str, BuiltinDispatch)] = &[
+    ("|", BuiltinDispatch::BlockComment),
+    (";", BuiltinDispatch::DatumComment),
+    ("(", BuiltinDispatch::Vector),
+    ("{", BuiltinDispatch::Set),
+    ("\\", BuiltinDispatch::Char),
+    ("xXbBoO0123456789", BuiltinDispatch::Radix),
+];
+
+fn builtin_dispatch(c: char) -> Option<BuiltinDispatch> {
+    BUILTIN_DISPATCH_CHARS
+        .iter()
+        .find(|(chars, _)| chars.contains(c))
+        .map(|(_, dispatch)| *dispatch)
+}
+
+/// Whether `#` followed by `c` has built-in meaning, so it can't be
+/// a reader macro.
+pub fn is_builtin_dispatch_char(c: char) -> bool {
+    builtin_dispatch(c).is_some()
+}
+
+// removes `_` digit separators, which are allowed only between digits
+// of given radix
+fn strip_separators(s: &str, radix: u32) -> Option<String> {
+    let chars: Vec<char> = s.chars().collect();
+
+    for (i, c) in chars.iter().enumerate() {
+        if *c == '_' {
+            let is_digit = |j: usize| chars.get(j).is_some_and(|c| c.is_digit(radix));
+            if i == 0 || !is_digit(i - 1) || !is_digit(i + 1) {
+                return None;
+            }
+        }
+    }
+
+    Some(chars.into_iter().filter(|c| *c != '_').collect())
 }
 
-impl<'a, T: Read> Lexer<'a, T> {
-    pub fn create(r: &'a mut T) -> Lexer<'a, T> {
+/// Printed forms of float values that have no digits, read back as
+/// these values.
+pub const SPECIAL_FLOATS: &[(&str, f64)] = &[
+    ("+inf.0", f64::INFINITY),
+    ("-inf.0", f64::NEG_INFINITY),
+    ("+nan.0", f64::NAN),
+];
+
+// parses token as decimal integer or float, `None` means it's a symbol
+fn parse_number(token: &str) -> Option<Token> {
+    if let Some((_, x)) = SPECIAL_FLOATS.iter().find(|(name, _)| *name == token) {
+        return Some(Token::FloatLiteral(*x));
+    }
+
+    let chars: Vec<char> = token.chars().collect();
+    let is_digit = |i: usize| chars.get(i).is_some_and(|c| c.is_ascii_digit());
+    let skip_digits = |i: &mut usize| {
+        while is_digit(*i) || chars.get(*i) == Some(&'_') {
+            *i += 1;
+        }
+    };
+
+    let mut i = 0;
+    if let Some('+') | Some('-') = chars.first() {
+        i += 1;
+    }
+
+    if !is_digit(i) {
+        return None;
+    }
+    skip_digits(&mut i);
+
+    let mut is_float = false;
+
+    if chars.get(i) == Some(&'.') {
+        i += 1;
+        if !is_digit(i) {
+            return None;
+        }
+        skip_digits(&mut i);
+        is_float = true;
+    }
+
+    if let Some('e') | Some('E') = chars.get(i) {
+        i += 1;
+        if let Some('+') | Some('-') = chars.get(i) {
+            i += 1;
+        }
+        if !is_digit(i) {
+            return None;
+        }
+        skip_digits(&mut i);
+        is_float = true;
+    }
+
+    if i != chars.len() {
+        return None;
+    }
+
+    let s = strip_separators(token, 10)?;
+
+    if is_float {
+        s.parse::<f64>().ok().map(Token::FloatLiteral)
+    } else {
+        match s.parse::<i64>() {
+            Ok(i) => Some(Token::IntegerLiteral(i)),
+            Err(_) => BigInt::parse_radix(&s, 10).map(Token::BigIntegerLiteral),
+        }
+    }
+}
+
+pub struct Lexer<T: Read> {
+    pbr: PushbackReader<T>,
+    file: Rc<String>,
+    // position of the next char
+    line: usize,
+    column: usize,
+    // column after the last char of previous line, restored when
+    // newline is unread
+    prev_line_end: usize,
+    token_start: (usize, usize),
+    // number of read chars
+    offset: usize,
+}
+
+impl<T: Read> Lexer<T> {
+    pub fn create(r: T) -> Lexer<T> {
+        Self::create_with_file(r, "<input>")
+    }
+
+    pub fn create_with_file(r: T, file: impl Into<String>) -> Lexer<T> {
         Lexer {
             pbr: PushbackReader::create(r),
+            file: Rc::new(file.into()),
+            line: 1,
+            column: 1,
+            prev_line_end: 1,
+            token_start: (1, 1),
+            offset: 0,
         }
     }
 
+    /// Location of the next char.
+    pub fn location(&self) -> SourceLocation {
+        SourceLocation::new(self.file.clone(), self.line, self.column)
+    }
+
+    /// Location of the first char of the last read token.
+    pub fn token_location(&self) -> SourceLocation {
+        let (line, column) = self.token_start;
+        SourceLocation::new(self.file.clone(), line, column)
+    }
+
+    // `#` is allowed in symbols, but at the start of token it begins
+    // dispatch syntax
     fn valid_symbol_char(c: char) -> bool {
-        c.is_alphanumeric()
-            || vec!['&', '*', '-', '?', '+', '<', '>']
-                .into_iter()
-                .find(|x| c == *x)
-                .is_some()
+        !c.is_whitespace() && !"()[]{}'`,\";".contains(c)
+    }
+
+    fn invalid_utf8() -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, "invalid UTF-8 in input")
+    }
+
+    // length of UTF-8 sequence determined by its first byte
+    fn utf8_seq_len(first_byte: u8) -> Option<usize> {
+        match first_byte {
+            0x00..=0x7f => Some(1),
+            0xc2..=0xdf => Some(2),
+            0xe0..=0xef => Some(3),
+            0xf0..=0xf4 => Some(4),
+            _ => None,
+        }
     }
 
     fn next_char(&mut self) -> io::Result<char> {
-        let mut one_byte: [u8; 1] = [0];
-        self.pbr.read_exact(&mut one_byte)
+        let mut bytes: [u8; 4] = [0; 4];
+        self.pbr.read_exact(&mut bytes[..1])
             .map_err(|e| {
                 // replace not informative 'failed to fill buffer' message
                 if e.kind() == io::ErrorKind::UnexpectedEof {
@@ -52,49 +253,216 @@ impl<'a, T: Read> Lexer<'a, T> {
                     e
                 }
             })?;
-        Ok(one_byte[0] as char)
+
+        let len = Self::utf8_seq_len(bytes[0]).ok_or_else(Self::invalid_utf8)?;
+
+        // input ending in the middle of a char is not a valid UTF-8
+        self.pbr.read_exact(&mut bytes[1..len]).map_err(|e| {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                Self::invalid_utf8()
+            } else {
+                e
+            }
+        })?;
+
+        let c = std::str::from_utf8(&bytes[..len])
+            .map(|s| s.chars().next().unwrap())
+            .map_err(|_| Self::invalid_utf8())?;
+
+        if c == '\n' {
+            self.prev_line_end = self.column;
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        self.offset += 1;
+
+        Ok(c)
     }
 
     fn unread_char(&mut self, c: char) {
-        self.pbr.unread_byte(c as u8);
+        let mut bytes: [u8; 4] = [0; 4];
+        self.pbr.unread(c.encode_utf8(&mut bytes).as_bytes());
+
+        if c == '\n' {
+            self.line -= 1;
+            self.column = self.prev_line_end;
+        } else {
+            self.column -= 1;
+        }
+        self.offset -= 1;
+    }
+
+    // reads the part of `\u{...}` escape after `u`
+    fn read_unicode_escape(&mut self) -> GenResult<char> {
+        if self.next_char()? != '{' {
+            Err(SyntaxError::new("expected { in unicode escape"))?
+        }
+
+        let mut digits = String::new();
+        loop {
+            match self.next_char()? {
+                '}' => break,
+                c if c.is_ascii_hexdigit() && digits.len() < 6 => digits.push(c),
+                c => Err(SyntaxError::new(format!(
+                    "unexpected char {} in unicode escape",
+                    c
+                )))?,
+            }
+        }
+
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(std::char::from_u32)
+            .ok_or_else(|| {
+                SyntaxError::new(format!("invalid unicode escape \\u{{{}}}", digits)).into()
+            })
     }
 
-    fn read_string_literal(&mut self) -> io::Result<String> {
+    fn read_string_literal(&mut self) -> GenResult<String> {
         let mut buf = Vec::new();
         loop {
-            let c = self.next_char()?;
+            let c = match self.next_char()? {
+                '"' => break,
+                '\\' => match self.next_char()? {
+                    '"' => '"',
+                    '\\' => '\\',
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    'u' => self.read_unicode_escape()?,
+                    c => Err(SyntaxError::new(format!(
+                        "unknown escape \\{} in string",
+                        c
+                    )))?,
+                },
+                c => c,
+            };
+            buf.push(c);
+        }
+
+        Ok(buf.into_iter().collect())
+    }
+
+    fn peek_char(&mut self) -> io::Result<Option<char>> {
+        let c = self.next_char();
 
-            // TODO: handle escaping
-            if c == '"' {
+        if is_eof(&c) {
+            return Ok(None);
+        }
+
+        let c = c?;
+        self.unread_char(c);
+        Ok(Some(c))
+    }
+
+    // reads decimal digits along with `_` separators
+    fn read_digits(&mut self, buf: &mut Vec<char>) -> io::Result<()> {
+        while let Some(c) = self.peek_char()? {
+            if !c.is_ascii_digit() && c != '_' {
                 break;
             }
-            buf.push(c);
+
+            buf.push(self.next_char()?);
         }
 
-        Ok(buf.into_iter().collect())
+        Ok(())
     }
 
-    fn read_integer_literal(&mut self) -> io::Result<i64> {
+    fn read_radix(&mut self) -> GenResult<u32> {
+        let radix = match self.next_char()? {
+            'x' | 'X' => 16,
+            'b' | 'B' => 2,
+            'o' | 'O' => 8,
+            c if c.is_ascii_digit() => {
+                let mut buf = vec![c];
+                self.read_digits(&mut buf)?;
+                let radix: String = buf.into_iter().collect();
+
+                match self.next_char()? {
+                    'r' | 'R' => (),
+                    c => Err(SyntaxError::new(format!(
+                        "unexpected char {} in radix literal",
+                        c
+                    )))?,
+                }
+
+                radix
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|r| (2..=36).contains(r))
+                    .ok_or_else(|| SyntaxError::new(format!("invalid radix {}", radix)))?
+            }
+            c => Err(SyntaxError::new(format!("unexpected char {} after #", c)))?,
+        };
+
+        Ok(radix)
+    }
+
+    // reads `#x1F`, `#b1010`, `#o17` or `#36rZZ` literal after `#`
+    fn read_radix_literal(&mut self) -> GenResult<Token> {
+        let radix = self.read_radix()?;
+
         let mut buf = Vec::new();
-        loop {
-            let c = self.next_char();
+        if let Some('+') | Some('-') = self.peek_char()? {
+            buf.push(self.next_char()?);
+        }
 
-            if is_eof(&c) {
+        while let Some(c) = self.peek_char()? {
+            if !c.is_ascii_alphanumeric() && c != '_' {
                 break;
             }
 
-            let c = c?;
+            buf.push(self.next_char()?);
+        }
 
-            if c.is_numeric() {
-                buf.push(c);
-            } else {
-                self.unread_char(c);
-                break;
+        let literal: String = buf.into_iter().collect();
+        let invalid_literal = || {
+            SyntaxError::new(format!(
+                "invalid number literal {} in radix {}",
+                literal, radix
+            ))
+        };
+
+        let s = strip_separators(&literal, radix).ok_or_else(invalid_literal)?;
+        let b = BigInt::parse_radix(&s, radix).ok_or_else(invalid_literal)?;
+
+        match b.to_i64() {
+            Some(i) => Ok(Token::IntegerLiteral(i)),
+            None => Ok(Token::BigIntegerLiteral(b)),
+        }
+    }
+
+    // reads `#\a`, `#\space` or `#\x3bb` literal after `#\`
+    fn read_char_literal(&mut self) -> GenResult<Token> {
+        let mut buf = vec![self.next_char()?];
+
+        if buf[0].is_alphanumeric() {
+            while let Some(c) = self.peek_char()? {
+                if !c.is_alphanumeric() {
+                    break;
+                }
+
+                buf.push(self.next_char()?);
             }
         }
 
-        let s: String = buf.into_iter().collect();
-        Ok(s.parse::<i64>().unwrap())
+        if buf.len() == 1 {
+            return Ok(Token::CharLiteral(buf[0]));
+        }
+
+        let name: String = buf.into_iter().collect();
+
+        if let Some((_, c)) = CHAR_NAMES.iter().find(|(n, _)| *n == name) {
+            return Ok(Token::CharLiteral(*c));
+        }
+
+        name.strip_prefix('x')
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .and_then(std::char::from_u32)
+            .map(Token::CharLiteral)
+            .ok_or_else(|| SyntaxError::new(format!("unknown character #\\{}", name)).into())
     }
 
     fn read_symbol(&mut self) -> io::Result<String> {
@@ -120,15 +488,90 @@ impl<'a, T: Read> Lexer<'a, T> {
     }
 
     fn skip_line(&mut self) -> io::Result<()> {
-        let mut next_char = self.next_char()?;
-        while next_char != '\n' {
-            next_char = self.next_char()?;
+        loop {
+            let c = self.next_char();
+            if is_eof(&c) || c? == '\n' {
+                return Ok(());
+            }
+        }
+    }
+
+    // skips `#| ... |#` comment after `#|`, nested comments are
+    // skipped along with the outer one
+    fn skip_block_comment(&mut self) -> GenResult<()> {
+        let mut depth = 1;
+        let mut prev = None;
+
+        while depth > 0 {
+            let c = self.next_char();
+            if is_eof(&c) {
+                Err(SyntaxError::new("unterminated block comment"))?
+            }
+
+            let c = c?;
+            prev = match (prev, c) {
+                (Some('|'), '#') => {
+                    depth -= 1;
+                    None
+                }
+                (Some('#'), '|') => {
+                    depth += 1;
+                    None
+                }
+                _ => Some(c),
+            };
         }
 
         Ok(())
     }
 
+    /// Reads next token, returns `None` at the end of input. Malformed
+    /// input is reported as `SyntaxError` with location, input ending
+    /// in the middle of a token as `EndOfInput` with its location.
     pub fn next_token(&mut self) -> GenResult<Option<Token>> {
+        self.read_token().map_err(|e| self.locate_error(e))
+    }
+
+    // reports invalid UTF-8 as syntax error, gives syntax errors
+    // current location and end of input location of unfinished token
+    fn locate_error(&mut self, e: Box<dyn Error>) -> Box<dyn Error> {
+        let kind = e.downcast_ref::<io::Error>().map(io::Error::kind);
+
+        let e: Box<dyn Error> = match kind {
+            Some(io::ErrorKind::InvalidData) => {
+                // invalid bytes are consumed, but not counted by next_char
+                self.column += 1;
+                Box::new(SyntaxError::new(e.to_string()))
+            }
+            Some(io::ErrorKind::UnexpectedEof) => {
+                Box::new(EndOfInput::new(true).with_location(self.token_location()))
+            }
+            _ => e,
+        };
+
+        match e.downcast::<SyntaxError>() {
+            Ok(e) => Box::new(e.with_location(self.location())),
+            Err(e) => e,
+        }
+    }
+
+    /// Skips the rest of current line, unless the last read char was
+    /// a newline. Used to resume reading after a malformed form.
+    pub fn skip_rest_of_line(&mut self) -> io::Result<()> {
+        if self.column == 1 {
+            return Ok(());
+        }
+
+        loop {
+            let c = self.next_char();
+            if is_eof(&c) || c? == '\n' {
+                return Ok(());
+            }
+        }
+    }
+
+    fn read_token(&mut self) -> GenResult<Option<Token>> {
+        self.token_start = (self.line, self.column);
         let c = self.next_char();
 
         if is_eof(&c) {
@@ -138,25 +581,78 @@ impl<'a, T: Read> Lexer<'a, T> {
         let c = c?;
 
         if c.is_whitespace() {
-            return self.next_token();
+            return self.read_token();
         }
 
         let tok = match c {
             ';' => {
                 self.skip_line()?;
-                return self.next_token();
+                return self.read_token();
             }
             '(' => Token::LeftPar,
             ')' => Token::RightPar,
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            '{' => Token::LeftBrace,
+            '}' => Token::RightBrace,
+            '\'' => Token::Quote,
+            '`' => Token::Quasiquote,
+            ',' => {
+                let next = self.next_char();
+                if is_eof(&next) {
+                    Token::Unquote
+                } else {
+                    match next? {
+                        '@' => Token::UnquoteSplicing,
+                        c => {
+                            self.unread_char(c);
+                            Token::Unquote
+                        }
+                    }
+                }
+            }
 
-            c if c.is_numeric() => {
-                self.unread_char(c);
-                Token::IntegerLiteral(self.read_integer_literal()?)
+            '#' => {
+                let next = self.peek_char()?;
+                match (next, next.and_then(builtin_dispatch)) {
+                    (_, Some(BuiltinDispatch::BlockComment)) => {
+                        self.next_char()?;
+                        self.skip_block_comment()?;
+                        return self.read_token();
+                    }
+                    (_, Some(BuiltinDispatch::DatumComment)) => {
+                        self.next_char()?;
+                        Token::DatumComment
+                    }
+                    (_, Some(BuiltinDispatch::Vector)) => {
+                        self.next_char()?;
+                        Token::HashLeftPar
+                    }
+                    (_, Some(BuiltinDispatch::Set)) => {
+                        self.next_char()?;
+                        Token::HashLeftBrace
+                    }
+                    (_, Some(BuiltinDispatch::Char)) => {
+                        self.next_char()?;
+                        self.read_char_literal()?
+                    }
+                    (Some(c), None) if !c.is_whitespace() => {
+                        self.next_char()?;
+                        Token::DispatchMacro(c)
+                    }
+                    // radix literal or error for `#` followed by whitespace
+                    _ => self.read_radix_literal()?,
+                }
             }
 
             c if Self::valid_symbol_char(c) => {
                 self.unread_char(c);
-                Token::Symbol(self.read_symbol()?)
+                let token = self.read_symbol()?;
+                if token == ":" {
+                    Err(SyntaxError::new("keyword without name"))?
+                }
+
+                parse_number(&token).unwrap_or(Token::Symbol(token))
             }
 
             '"' => Token::StringLiteral(self.read_string_literal()?),
@@ -167,6 +663,37 @@ impl<'a, T: Read> Lexer<'a, T> {
     }
 }
 
+impl<T: Read> Input for Lexer<T> {
+    fn next_token(&mut self) -> GenResult<Option<Token>> {
+        Lexer::next_token(self)
+    }
+
+    fn token_location(&self) -> SourceLocation {
+        Lexer::token_location(self)
+    }
+
+    fn skip_rest_of_line(&mut self) -> io::Result<()> {
+        Lexer::skip_rest_of_line(self)
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn read_char(&mut self) -> GenResult<Option<char>> {
+        let c = self.next_char();
+        if is_eof(&c) {
+            return Ok(None);
+        }
+
+        c.map(Some).map_err(|e| self.locate_error(Box::new(e)))
+    }
+
+    fn peek_char(&mut self) -> GenResult<Option<char>> {
+        Lexer::peek_char(self).map_err(|e| self.locate_error(Box::new(e)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -197,6 +724,287 @@ mod tests {
         assert_eq!(lexer.next_token().unwrap().unwrap(), Token::IntegerLiteral(2019));
     }
 
+    #[test]
+    fn test_float_literal() {
+        let mut input =
+            "1.5 0.25 2e10 1E-3 3.5e+2 +inf.0 -inf.0 +nan.0 1.x 1. 1e .5 inf nan.0".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::FloatLiteral(1.5));
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::FloatLiteral(0.25));
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::FloatLiteral(2e10));
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::FloatLiteral(1e-3));
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::FloatLiteral(350.0));
+        assert_eq!(
+            lexer.next_token().unwrap().unwrap(),
+            Token::FloatLiteral(f64::INFINITY)
+        );
+        assert_eq!(
+            lexer.next_token().unwrap().unwrap(),
+            Token::FloatLiteral(f64::NEG_INFINITY)
+        );
+        match lexer.next_token().unwrap().unwrap() {
+            Token::FloatLiteral(x) => assert!(x.is_nan()),
+            tok => panic!("expected NaN, got {:?}", tok),
+        }
+
+        for s in &["1.x", "1.", "1e", ".5", "inf", "nan.0"] {
+            assert_eq!(
+                lexer.next_token().unwrap().unwrap(),
+                Token::Symbol(s.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_radix_literals() {
+        let mut input =
+            "#x1F #XfF #b1010 #o17 #36rZZ #2r-101 #x-80 #b1111_0000 #x1_0000_0000_0000_0000"
+                .as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        let mut next = || lexer.next_token().unwrap().unwrap();
+
+        assert_eq!(next(), Token::IntegerLiteral(31));
+        assert_eq!(next(), Token::IntegerLiteral(255));
+        assert_eq!(next(), Token::IntegerLiteral(10));
+        assert_eq!(next(), Token::IntegerLiteral(15));
+        assert_eq!(next(), Token::IntegerLiteral(1295));
+        assert_eq!(next(), Token::IntegerLiteral(-5));
+        assert_eq!(next(), Token::IntegerLiteral(-128));
+        assert_eq!(next(), Token::IntegerLiteral(240));
+        assert_eq!(
+            next(),
+            Token::BigIntegerLiteral(BigInt::parse_radix("18446744073709551616", 10).unwrap())
+        );
+
+        for s in &[
+            "#x", "#xg", "#b102", "#37r1", "#1r0", "#12x", "# 1", "#x_1", "#x1__0",
+        ] {
+            let mut input = s.as_bytes();
+            let mut lexer = Lexer::create(&mut input);
+            let err = lexer.next_token().unwrap_err();
+            assert!(err.downcast_ref::<SyntaxError>().is_some(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_char_literals() {
+        let mut input = r"#\a #\A #\( #\  #\space #\newline #\x3bb #\x #\λ #\1)".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        let mut next = || lexer.next_token().unwrap().unwrap();
+
+        assert_eq!(next(), Token::CharLiteral('a'));
+        assert_eq!(next(), Token::CharLiteral('A'));
+        assert_eq!(next(), Token::CharLiteral('('));
+        assert_eq!(next(), Token::CharLiteral(' '));
+        assert_eq!(next(), Token::CharLiteral(' '));
+        assert_eq!(next(), Token::CharLiteral('\n'));
+        assert_eq!(next(), Token::CharLiteral('λ'));
+        assert_eq!(next(), Token::CharLiteral('x'));
+        assert_eq!(next(), Token::CharLiteral('λ'));
+        assert_eq!(next(), Token::CharLiteral('1'));
+        assert_eq!(next(), Token::RightPar);
+
+        for s in &[r"#\foo", r"#\xd800", r"#\xfffffff", r"#\"] {
+            let mut input = s.as_bytes();
+            let mut lexer = Lexer::create(&mut input);
+            assert!(lexer.next_token().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_symbol_chars() {
+        let symbols = "set! string= a/b -> 1+ <= foo.bar %internal $x a#b |x| 1a 12abc 1e5x";
+        let mut input = symbols.as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        for s in symbols.split(' ') {
+            assert_eq!(
+                lexer.next_token().unwrap().unwrap(),
+                Token::Symbol(s.to_string())
+            );
+        }
+
+        let mut input = "foo(bar)baz'x;c\n\"s\"`y,z".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+        let mut next = || lexer.next_token().unwrap().unwrap();
+        let symbol = |s: &str| Token::Symbol(s.to_string());
+
+        assert_eq!(next(), symbol("foo"));
+        assert_eq!(next(), Token::LeftPar);
+        assert_eq!(next(), symbol("bar"));
+        assert_eq!(next(), Token::RightPar);
+        assert_eq!(next(), symbol("baz"));
+        assert_eq!(next(), Token::Quote);
+        assert_eq!(next(), symbol("x"));
+        assert_eq!(next(), Token::StringLiteral("s".to_string()));
+        assert_eq!(next(), Token::Quasiquote);
+        assert_eq!(next(), symbol("y"));
+        assert_eq!(next(), Token::Unquote);
+        assert_eq!(next(), symbol("z"));
+    }
+
+    #[test]
+    fn test_keywords() {
+        let mut input = ":foo :a-b? a:b".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        let mut next = || lexer.next_token().unwrap().unwrap();
+
+        assert_eq!(next(), Token::Symbol(":foo".to_string()));
+        assert_eq!(next(), Token::Symbol(":a-b?".to_string()));
+        assert_eq!(next(), Token::Symbol("a:b".to_string()));
+
+        let mut input = ": foo".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+        let err = lexer.next_token().unwrap_err();
+        assert!(err.downcast_ref::<SyntaxError>().is_some());
+    }
+
+    #[test]
+    fn test_dispatch_macro() {
+        let mut input = "#!ab #d(1) #x10".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        assert_eq!(lexer.next_token().unwrap(), Some(Token::DispatchMacro('!')));
+        assert_eq!(Input::read_char(&mut lexer).unwrap(), Some('a'));
+        assert_eq!(Input::peek_char(&mut lexer).unwrap(), Some('b'));
+        assert_eq!(Input::read_char(&mut lexer).unwrap(), Some('b'));
+        assert_eq!(lexer.next_token().unwrap(), Some(Token::DispatchMacro('d')));
+        assert_eq!(lexer.next_token().unwrap(), Some(Token::LeftPar));
+        assert_eq!(lexer.next_token().unwrap(), Some(Token::IntegerLiteral(1)));
+        assert_eq!(lexer.next_token().unwrap(), Some(Token::RightPar));
+        assert_eq!(lexer.next_token().unwrap(), Some(Token::IntegerLiteral(16)));
+        assert_eq!(Input::read_char(&mut lexer).unwrap(), None);
+        assert_eq!(Input::peek_char(&mut lexer).unwrap(), None);
+        assert_eq!(Input::offset(&lexer), 15);
+
+        // builtin chars are never dispatched to reader macros
+        for c in BUILTIN_DISPATCH_CHARS
+            .iter()
+            .flat_map(|(chars, _)| chars.chars())
+        {
+            assert!(is_builtin_dispatch_char(c));
+
+            let s = format!("#{}1|# x", c);
+            let mut input = s.as_bytes();
+            let mut lexer = Lexer::create(&mut input);
+            if let Ok(tok) = lexer.next_token() {
+                assert_ne!(tok, Some(Token::DispatchMacro(c)), "{}", s);
+            }
+        }
+    }
+
+    #[test]
+    fn test_brackets() {
+        let mut input = "[a]#(b){c}#{d}#[".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+        let mut next = || lexer.next_token().unwrap();
+
+        assert_eq!(next(), Some(Token::LeftBracket));
+        assert_eq!(next(), Some(Token::Symbol("a".to_string())));
+        assert_eq!(next(), Some(Token::RightBracket));
+        assert_eq!(next(), Some(Token::HashLeftPar));
+        assert_eq!(next(), Some(Token::Symbol("b".to_string())));
+        assert_eq!(next(), Some(Token::RightPar));
+        assert_eq!(next(), Some(Token::LeftBrace));
+        assert_eq!(next(), Some(Token::Symbol("c".to_string())));
+        assert_eq!(next(), Some(Token::RightBrace));
+        assert_eq!(next(), Some(Token::HashLeftBrace));
+        assert_eq!(next(), Some(Token::Symbol("d".to_string())));
+        assert_eq!(next(), Some(Token::RightBrace));
+        assert_eq!(next(), Some(Token::DispatchMacro('[')));
+        assert_eq!(next(), None);
+    }
+
+    #[test]
+    fn test_block_comments() {
+        let mut input =
+            "1 ; line\n#| block |# 2 #| outer #| inner |# |# 3 #|a||#4 #;5 ; eof".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        let mut next = || lexer.next_token().unwrap();
+
+        assert_eq!(next(), Some(Token::IntegerLiteral(1)));
+        assert_eq!(next(), Some(Token::IntegerLiteral(2)));
+        assert_eq!(next(), Some(Token::IntegerLiteral(3)));
+        assert_eq!(next(), Some(Token::IntegerLiteral(4)));
+        assert_eq!(next(), Some(Token::DatumComment));
+        assert_eq!(next(), Some(Token::IntegerLiteral(5)));
+        assert_eq!(next(), None);
+
+        for s in &["#| foo", "#| #| |#"] {
+            let mut input = s.as_bytes();
+            let mut lexer = Lexer::create(&mut input);
+            let err = lexer.next_token().unwrap_err();
+            assert!(err.downcast_ref::<SyntaxError>().is_some(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_digit_separators() {
+        let mut input = "1_000_000 -1_0 1_000.000_1 1e1_0".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        let mut next = || lexer.next_token().unwrap().unwrap();
+
+        assert_eq!(next(), Token::IntegerLiteral(1_000_000));
+        assert_eq!(next(), Token::IntegerLiteral(-10));
+        assert_eq!(next(), Token::FloatLiteral(1_000.000_1));
+        assert_eq!(next(), Token::FloatLiteral(1e10));
+
+        // tokens with misplaced separators are symbols
+        for s in &["1_", "1__0", "1_.5", "_1", "1_e5", "1e_5", "1.5_e2"] {
+            let mut input = s.as_bytes();
+            let mut lexer = Lexer::create(&mut input);
+            assert_eq!(
+                lexer.next_token().unwrap().unwrap(),
+                Token::Symbol(s.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_signed_literals() {
+        let mut input =
+            "-5 +7 -1.5e3 -9223372036854775808 -9223372036854775809 - + -foo +x -5x".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        let mut next = || lexer.next_token().unwrap().unwrap();
+        let symbol = |s: &str| Token::Symbol(s.to_string());
+
+        assert_eq!(next(), Token::IntegerLiteral(-5));
+        assert_eq!(next(), Token::IntegerLiteral(7));
+        assert_eq!(next(), Token::FloatLiteral(-1500.0));
+        assert_eq!(next(), Token::IntegerLiteral(i64::MIN));
+        assert_eq!(
+            next(),
+            Token::BigIntegerLiteral(BigInt::from(i64::MIN).sub(&BigInt::from(1)))
+        );
+        assert_eq!(next(), symbol("-"));
+        assert_eq!(next(), symbol("+"));
+        assert_eq!(next(), symbol("-foo"));
+        assert_eq!(next(), symbol("+x"));
+        assert_eq!(next(), symbol("-5x"));
+    }
+
+    #[test]
+    fn test_bigint_literal() {
+        let mut input = "9223372036854775807 9223372036854775808".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        assert_eq!(
+            lexer.next_token().unwrap().unwrap(),
+            Token::IntegerLiteral(i64::MAX)
+        );
+        assert_eq!(
+            lexer.next_token().unwrap().unwrap(),
+            Token::BigIntegerLiteral(BigInt::from(i64::MAX).add(&BigInt::from(1)))
+        );
+    }
+
     #[test]
     fn test_string_literal() {
         let mut input = "\"\" \"foo\" \"bar\"".as_bytes();
@@ -216,6 +1024,105 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_utf8() {
+        let mut input = "(привет \"🦀 ü\" 1ж\u{3000}ж)".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::LeftPar);
+        assert_eq!(
+            lexer.next_token().unwrap().unwrap(),
+            Token::Symbol("привет".to_string())
+        );
+        assert_eq!(
+            lexer.next_token().unwrap().unwrap(),
+            Token::StringLiteral("🦀 ü".to_string())
+        );
+        // multibyte space after symbol is unread and then read again
+        assert_eq!(
+            lexer.next_token().unwrap().unwrap(),
+            Token::Symbol("1ж".to_string())
+        );
+        assert_eq!(
+            lexer.next_token().unwrap().unwrap(),
+            Token::Symbol("ж".to_string())
+        );
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::RightPar);
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let inputs: &[&[u8]] = &[b"\xff", b"\"\xd0\"", b"\xd0", b"\xed\xa0\x80", b"\xc0\xaf"];
+        for bytes in inputs {
+            let mut input = *bytes;
+            let mut lexer = Lexer::create(&mut input);
+            let err = lexer.next_token().unwrap_err();
+            assert!(err.downcast_ref::<SyntaxError>().is_some());
+        }
+    }
+
+    #[test]
+    fn test_locations() {
+        let mut input = "(foo\n  1 x \"a\nb\" 2\n)".as_bytes();
+        let mut lexer = Lexer::create_with_file(&mut input, "foo.unl");
+
+        let mut locations = vec![];
+        while lexer.next_token().unwrap().is_some() {
+            let location = lexer.token_location();
+            assert_eq!(*location.file, "foo.unl");
+            locations.push((location.line, location.column));
+        }
+
+        // x is read after unreading space, paren after unreading newline
+        assert_eq!(
+            locations,
+            vec![(1, 1), (1, 2), (2, 3), (2, 5), (2, 7), (3, 4), (4, 1)]
+        );
+    }
+
+    #[test]
+    fn test_error_location() {
+        let mut input = "(a\n \"\\x\")".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        lexer.next_token().unwrap();
+        lexer.next_token().unwrap();
+        let err = lexer.next_token().unwrap_err();
+        assert_eq!(
+            format!("{}", err),
+            "<input>:2:5: unknown escape \\x in string"
+        );
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let mut input = r#""a\"b\\c" "\n\t\r" "\u{41}\u{3bb}""#.as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        assert_eq!(
+            lexer.next_token().unwrap().unwrap(),
+            Token::StringLiteral("a\"b\\c".to_string())
+        );
+        assert_eq!(
+            lexer.next_token().unwrap().unwrap(),
+            Token::StringLiteral("\n\t\r".to_string())
+        );
+        assert_eq!(
+            lexer.next_token().unwrap().unwrap(),
+            Token::StringLiteral("A\u{3bb}".to_string())
+        );
+
+        for s in &[r#""\x""#, r#""\u41""#, r#""\u{}""#, r#""\u{d800}""#, r#""\u{4g}""#] {
+            let mut input = s.as_bytes();
+            let mut lexer = Lexer::create(&mut input);
+            assert!(lexer.next_token().is_err());
+        }
+
+        let mut input = r#""foo\"#.as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+        assert!(is_gen_eof(&lexer.next_token()));
+    }
+
     #[test]
     fn test_incomplete_string() {
         let mut input = "\"foo".as_bytes();
@@ -250,6 +1157,24 @@ mod tests {
         assert_eq!(lexer.next_token().unwrap().unwrap(), Token::LeftPar);
     }
 
+    #[test]
+    fn test_quote_chars() {
+        let mut input = "'x `(,y ,@z) , @".as_bytes();
+        let mut lexer = Lexer::create(&mut input);
+
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::Quote);
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::Symbol("x".to_string()));
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::Quasiquote);
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::LeftPar);
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::Unquote);
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::Symbol("y".to_string()));
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::UnquoteSplicing);
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::Symbol("z".to_string()));
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::RightPar);
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::Unquote);
+        assert_eq!(lexer.next_token().unwrap().unwrap(), Token::Symbol("@".to_string()));
+    }
+
     #[test]
     fn test_comments() {
         let mut input = ";; this is comment \n foo".as_bytes();
@@ -3,16 +3,20 @@ mod test_utils;
 #[cfg(test)]
 mod stdlib_test;
 
+pub mod bigint;
 pub mod common;
 pub mod cons;
 pub mod env;
 pub mod error;
 pub mod eval;
 pub mod lexer;
+pub mod location;
 pub mod macroexpand;
 pub mod native;
+pub mod number;
 pub mod object;
 pub mod print;
 pub mod pushback_reader;
 pub mod reader;
 pub mod special;
+pub mod stream;
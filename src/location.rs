@@ -0,0 +1,244 @@
+use crate::cons::List;
+use crate::cons::WeakList;
+use crate::object::LispObject;
+use crate::object::Symbol;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+/// Position in source text, lines and columns are counted from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: Rc<String>,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SourceLocation {
+    pub fn new(file: Rc<String>, line: usize, column: usize) -> Self {
+        Self { file, line, column }
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+// entries of lists that are no longer alive are dropped once the
+// table grows twice as big as it was after the last purge
+const MIN_PURGE_SIZE: usize = 1024;
+
+#[derive(Debug, Default)]
+struct Locations {
+    // weak references keep cells of dead lists from being reused
+    // while their entries are still in the table
+    entries: HashMap<usize, (WeakList<LispObject>, SourceLocation)>,
+    // locations of symbols, keyed by identity of the cons cell whose
+    // first element they are
+    symbols: HashMap<usize, (WeakList<LispObject>, SourceLocation)>,
+    purge_size: usize,
+}
+
+impl Locations {
+    fn len(&self) -> usize {
+        self.entries.len() + self.symbols.len()
+    }
+
+    fn purge_dead(&mut self) {
+        self.entries.retain(|_, (list, _)| list.is_alive());
+        self.symbols.retain(|_, (list, _)| list.is_alive());
+        self.purge_size = MIN_PURGE_SIZE.max(self.len() * 2);
+    }
+
+    fn make_room(&mut self) {
+        if self.len() >= self.purge_size {
+            self.purge_dead();
+        }
+    }
+}
+
+/// Side table with locations of list forms, keyed by identity of the
+/// first cons cell. The table doesn't keep lists alive, their entries
+/// are dropped some time after the lists are. Clones share the same
+/// table.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    locations: Rc<RefCell<Locations>>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, list: &List<LispObject>, location: SourceLocation) {
+        if let (Some(id), Some(weak)) = (list.cell_id(), list.downgrade()) {
+            let mut locations = self.locations.borrow_mut();
+            locations.make_room();
+            locations.entries.insert(id, (weak, location));
+        }
+    }
+
+    /// Records location of the symbol which is the first element of
+    /// `cell`.
+    pub fn insert_symbol(&self, cell: &List<LispObject>, location: SourceLocation) {
+        if let (Some(id), Some(weak)) = (cell.cell_id(), cell.downgrade()) {
+            let mut locations = self.locations.borrow_mut();
+            locations.make_room();
+            locations.symbols.insert(id, (weak, location));
+        }
+    }
+
+    fn get_symbol(&self, cell: &List<LispObject>) -> Option<SourceLocation> {
+        let id = cell.cell_id()?;
+        self.locations
+            .borrow()
+            .symbols
+            .get(&id)
+            .map(|(_, location)| location.clone())
+    }
+
+    /// Location of the first occurrence of `sym` in `form` or in its
+    /// sublists, except for quoted ones.
+    pub fn find_symbol(&self, form: &List<LispObject>, sym: &Symbol) -> Option<SourceLocation> {
+        let mut cell = form.clone();
+
+        while let Some(x) = cell.first() {
+            let location = match x {
+                LispObject::Symbol(s) if s.ptr_eq(sym) => self.get_symbol(&cell),
+                LispObject::List(list) if !is_quoted(list) => self.find_symbol(list, sym),
+                _ => None,
+            };
+
+            if location.is_some() {
+                return location;
+            }
+
+            cell = cell.tail();
+        }
+
+        None
+    }
+
+    pub fn get(&self, list: &List<LispObject>) -> Option<SourceLocation> {
+        let id = list.cell_id()?;
+        self.locations
+            .borrow()
+            .entries
+            .get(&id)
+            .map(|(_, location)| location.clone())
+    }
+
+    #[cfg(test)]
+    pub fn entries_count(&self) -> usize {
+        self.locations.borrow().entries.len()
+    }
+
+    /// Gives `to` location of `from`, unless `to` already has one.
+    pub fn inherit(&self, from: &List<LispObject>, to: &List<LispObject>) {
+        if self.get(to).is_none() {
+            if let Some(location) = self.get(from) {
+                self.insert(to, location);
+            }
+        }
+    }
+
+    /// Gives symbols among elements of `to` locations of the same
+    /// symbols at the same positions in `from`.
+    pub fn inherit_symbols(&self, from: &List<LispObject>, to: &List<LispObject>) {
+        let (mut from, mut to) = (from.clone(), to.clone());
+
+        while let (Some(x), Some(y)) = (from.first(), to.first()) {
+            if let (LispObject::Symbol(x), LispObject::Symbol(y)) = (x, y) {
+                if let Some(location) = self.get_symbol(&from).filter(|_| x.ptr_eq(y)) {
+                    self.insert_symbol(&to, location);
+                }
+            }
+
+            from = from.tail();
+            to = to.tail();
+        }
+    }
+}
+
+fn is_quoted(list: &List<LispObject>) -> bool {
+    match list.first() {
+        Some(LispObject::Symbol(s)) => s.name() == "quote",
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity() {
+        let map = SourceMap::new();
+        let loc = SourceLocation::new(Rc::new("foo.unl".to_string()), 2, 3);
+        let list = List::empty().cons(LispObject::Integer(1));
+        let equal_list = List::empty().cons(LispObject::Integer(1));
+
+        map.insert(&list, loc.clone());
+        assert_eq!(map.get(&list), Some(loc.clone()));
+        assert_eq!(map.get(&list.clone()), Some(loc.clone()));
+        assert_eq!(map.get(&equal_list), None);
+        assert_eq!(format!("{}", loc), "foo.unl:2:3");
+
+        map.inherit(&list, &equal_list);
+        assert_eq!(map.get(&equal_list), Some(loc));
+    }
+
+    #[test]
+    fn test_find_symbol() {
+        let map = SourceMap::new();
+        let file = Rc::new("foo.unl".to_string());
+        let (foo, bar) = (Symbol::new("foo"), Symbol::new("bar"));
+        let inner = List::empty().cons(LispObject::Symbol(foo.clone()));
+        let quoted = List::empty()
+            .cons(LispObject::Symbol(foo.clone()))
+            .cons(LispObject::Symbol(Symbol::new("quote")));
+        let form = List::empty()
+            .cons(LispObject::List(inner.clone()))
+            .cons(LispObject::List(quoted.clone()));
+
+        map.insert_symbol(&quoted.tail(), SourceLocation::new(file.clone(), 1, 1));
+        assert_eq!(map.find_symbol(&form, &foo), None);
+
+        map.insert_symbol(&inner, SourceLocation::new(file.clone(), 1, 2));
+        assert_eq!(
+            map.find_symbol(&form, &foo),
+            Some(SourceLocation::new(file.clone(), 1, 2))
+        );
+        assert_eq!(map.find_symbol(&form, &bar), None);
+
+        let copy = List::empty()
+            .cons(LispObject::Symbol(foo.clone()))
+            .cons(LispObject::Symbol(foo.clone()));
+        map.inherit_symbols(&quoted, &copy);
+        assert_eq!(map.get_symbol(&copy), None);
+        assert_eq!(
+            map.get_symbol(&copy.tail()),
+            Some(SourceLocation::new(file, 1, 1))
+        );
+    }
+
+    #[test]
+    fn test_dead_lists_dropped() {
+        let map = SourceMap::new();
+        let loc = SourceLocation::new(Rc::new("foo.unl".to_string()), 1, 1);
+        let live = List::empty().cons(LispObject::Integer(0));
+        map.insert(&live, loc.clone());
+
+        for i in 0..10 * MIN_PURGE_SIZE {
+            let list = List::empty().cons(LispObject::Integer(i as i64));
+            map.insert(&list, loc.clone());
+        }
+
+        assert!(map.locations.borrow().len() <= MIN_PURGE_SIZE + 1);
+        assert_eq!(map.get(&live), Some(loc));
+    }
+}
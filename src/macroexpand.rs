@@ -3,6 +3,7 @@ use crate::env::Env;
 use crate::error;
 use crate::eval;
 use crate::eval::EvalResult;
+use crate::object;
 use crate::object::LispObject;
 use crate::object::Symbol;
 use crate::special;
@@ -16,19 +17,46 @@ fn macroexpand_list(
         .map(|lo| macroexpand_all(env.clone(), lo))
         .collect::<Result<Vec<_>, _>>()?;
 
-    Ok(List::from_rev_iter(expanded))
+    let expanded = List::from_rev_iter(expanded);
+    env.source_map().inherit_symbols(list, &expanded);
+    Ok(expanded)
 }
 
 fn macroexpand_into_list(env: &Env, list: &List<LispObject>) -> EvalResult {
     Ok(LispObject::List(macroexpand_list(env, list)?))
 }
 
-pub fn macroexpand_all(env: Env, form: &LispObject) -> EvalResult {
+pub fn macroexpand_all(mut env: Env, form: &LispObject) -> EvalResult {
+    let list = match form {
+        LispObject::List(list) if !list.is_empty() => list,
+        _ => return macroexpand_form(env, form),
+    };
+
+    env.track_location(list);
+    let expanded = macroexpand_form(env.clone(), form)?;
+
+    // expanded form is reported at the location of original one
+    if let LispObject::List(expanded_list) = &expanded {
+        env.source_map().inherit(list, expanded_list);
+    }
+
+    Ok(expanded)
+}
+
+fn macroexpand_form(env: Env, form: &LispObject) -> EvalResult {
     match form {
         self_expand @ LispObject::T
         | self_expand @ LispObject::Integer(_)
+        | self_expand @ LispObject::BigInteger(_)
+        | self_expand @ LispObject::Float(_)
         | self_expand @ LispObject::String(_)
+        | self_expand @ LispObject::Char(_)
+        | self_expand @ LispObject::Vector(_)
+        | self_expand @ LispObject::Map(_)
+        | self_expand @ LispObject::Set(_)
+        | self_expand @ LispObject::Box(_)
         | self_expand @ LispObject::Fn(_)
+        | self_expand @ LispObject::Stream(_)
         | self_expand @ LispObject::Symbol(_) => Ok(self_expand.clone()),
 
         LispObject::List(list) if list.is_empty() => Ok(LispObject::nil()),
@@ -71,13 +99,22 @@ pub fn macroexpand_all(env: Env, form: &LispObject) -> EvalResult {
                     let expanded_body = macroexpand_list(&env, &body)?;
 
                     let mut expanded_bindings = List::empty();
+                    // binding clauses were checked to be lists by parse_let
+                    let binding_lists = object::to_list(let_forms.ufirst())
+                        .unwrap()
+                        .iter()
+                        .map(|binding| object::to_list(binding).unwrap().clone())
+                        .collect::<Vec<_>>();
 
                     // implementation with try_fold cannot be compiled for some reason
-                    for (sym, val_form) in bindings.into_iter().rev() {
+                    for ((sym, val_form), binding) in bindings.into_iter().zip(binding_lists).rev()
+                    {
                         let expanded_val = macroexpand_all(env.clone(), val_form)?;
                         let reconstructed_binding = List::empty()
                             .cons(expanded_val)
                             .cons(LispObject::Symbol(sym));
+                        env.source_map()
+                            .inherit_symbols(&binding, &reconstructed_binding);
                         expanded_bindings =
                             expanded_bindings.cons(LispObject::List(reconstructed_binding));
                     }
@@ -2,12 +2,28 @@ use crate::cons::List;
 use crate::env;
 use crate::error;
 use crate::eval;
-use crate::eval::EvalResult;
+use crate::eval::TailCall;
+use crate::eval::TailCallResult;
+use crate::lexer;
+use crate::number;
+use crate::number::Number;
 use crate::object;
 use crate::object::LispObject;
+use crate::object::Map;
+use crate::object::MutableBox;
+use crate::object::Set;
 use crate::object::Symbol;
+use crate::reader::Reader;
+use crate::stream::Stream;
+use im::HashMap;
+use im::HashSet;
+use im::Vector;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
 use std::error::Error;
 use std::fmt;
+use std::fs;
+use std::io;
 use std::io::Write;
 
 #[derive(Debug)]
@@ -32,14 +48,14 @@ fn identity(v: LispObject) -> LispObject {
 macro_rules! define_native_fn {
     ($maker:ident, $id:ident ($env:ident, $( $arg:ident : $converter:path ),*) -> $result_wrap:path $body:block) => {
         #[allow(unused_mut)]
-        fn $id( $env: env::Env, args: List<LispObject> ) -> EvalResult {
+        fn $id( $env: env::Env, args: List<LispObject> ) -> TailCallResult {
             let mut args = args.iter();
 
             $( let $arg = $env.attach_st($converter(args.next().unwrap()))?; )*
 
             #[allow(unused)]
             let res = $result_wrap($body);
-            Ok(res)
+            Ok(TailCall::Done(res))
         }
 
         fn $maker(name: impl Into<String>) -> object::Function {
@@ -52,7 +68,7 @@ macro_rules! define_native_fn {
 
     ($maker:ident, $id:ident ($env:ident, $( $arg:ident : $converter:path, )* ... $vararg:ident : $vconverter:path ) -> $result_wrap:path $body:block) => {
         #[allow(unused_mut)]
-        fn $id( $env: env::Env, args: List<LispObject> ) -> EvalResult {
+        fn $id( $env: env::Env, args: List<LispObject> ) -> TailCallResult {
             let mut args = args.iter();
 
             $(  let $arg = $env.attach_st($converter(args.next().unwrap()))?; )*
@@ -62,7 +78,7 @@ macro_rules! define_native_fn {
                 .collect::<Result<List<_>, _>>()?;
 
             let res = $result_wrap($body);
-            Ok(res)
+            Ok(TailCall::Done(res))
         }
 
         fn $maker(name: impl Into<String>) -> object::Function {
@@ -71,6 +87,33 @@ macro_rules! define_native_fn {
             let restarg = Some(Symbol::new(stringify!($vararg)));
             object::Function::new_native(name, args , restarg, object::NativeFnWrapper($id))
         }
+    };
+
+    ($maker:ident, $id:ident ($env:ident, $( $arg:ident : $converter:path, )* [ $( $opt:ident : $oconverter:path ),* ] ) -> $result_wrap:path $body:block) => {
+        #[allow(unused_mut)]
+        fn $id( $env: env::Env, args: List<LispObject> ) -> TailCallResult {
+            let mut args = args.iter();
+
+            $( let $arg = $env.attach_st($converter(args.next().unwrap()))?; )*
+
+            $(
+                let $opt = match args.next() {
+                    Some(lo) => Some($env.attach_st($oconverter(lo))?),
+                    None => None,
+                };
+            )*
+
+            let res = $result_wrap($body);
+            Ok(TailCall::Done(res))
+        }
+
+        fn $maker(name: impl Into<String>) -> object::Function {
+            let name = Some(Symbol::new(name));
+            let args = List::from_rev_iter(vec![$( Symbol::new(stringify!($arg)), )* $( Symbol::new(stringify!($opt)), )*]);
+            let mut f = object::Function::new_native(name, args, None, object::NativeFnWrapper($id));
+            f.sig.optional = [$( stringify!($opt), )*].len();
+            f
+        }
     }
 }
 
@@ -114,7 +157,7 @@ define_native_fn! {
     }
 }
 
-fn native_apply(env: env::Env, args: List<LispObject>) -> EvalResult {
+fn native_apply(mut env: env::Env, args: List<LispObject>) -> TailCallResult {
     let f = env.attach_st(object::to_function(args.first().unwrap()))?;
 
     let args = args.tail();
@@ -130,7 +173,9 @@ fn native_apply(env: env::Env, args: List<LispObject>) -> EvalResult {
         args = args.cons_rc(x);
     }
 
-    eval::call_function_object(env, f, args, false, None)
+    // apply calls function in tail position, so its own frame is replaced
+    env.mark_tail_frame();
+    eval::call_function_object_tail(env, f, args, false, None)
 }
 
 fn make_apply(name: impl Into<String>) -> object::Function {
@@ -146,54 +191,164 @@ fn make_apply(name: impl Into<String>) -> object::Function {
 
 define_native_fn! {
     make_add,
-    native_add(_env, ... args: object::to_i64) -> LispObject::Integer {
-        let mut res = 0;
-        for arg in args.iter() {
-            res += *arg
-        }
-        res
+    native_add(_env, ... args: number::to_number) -> LispObject::from {
+        args.iter().fold(Number::Integer(0), |acc, x| number::add(&acc, x))
     }
 }
 
 define_native_fn! {
     make_sub,
-    native_sub(_env, from: object::to_i64, ... args: object::to_i64) -> LispObject::Integer {
-        let mut res = *from;
+    native_sub(_env, from: number::to_number, ... args: number::to_number) -> LispObject::from {
+        if args.is_empty() {
+            number::neg(&from)
+        } else {
+            args.iter().fold(from, |acc, x| number::sub(&acc, x))
+        }
+    }
+}
 
+define_native_fn! {
+    make_mul,
+    native_mul(_env, ... args: number::to_number) -> LispObject::from {
+        args.iter().fold(Number::Integer(1), |acc, x| number::mul(&acc, x))
+    }
+}
+
+define_native_fn! {
+    make_div,
+    native_div(env, from: number::to_number, ... args: number::to_number) -> LispObject::from {
         if args.is_empty() {
-            res = -res;
+            env.attach_st(number::div(&Number::Integer(1), &from))?
         } else {
-            for arg in args.iter() {
-                res -= *arg
-            }
+            env.attach_st(args.iter().try_fold(from, |acc, x| number::div(&acc, x)))?
         }
+    }
+}
 
-        res
+define_native_fn! {
+    make_quot,
+    native_quot(env, x: number::to_number, y: number::to_number) -> LispObject::from {
+        env.attach_st(number::quot(&x, &y))?
     }
 }
 
 define_native_fn! {
-    make_mul,
-    native_mul(_env, ... args: object::to_i64) -> LispObject::Integer {
-        let mut res = 1;
-        for arg in args.iter() {
-            res *= *arg
+    make_rem,
+    native_rem(env, x: number::to_number, y: number::to_number) -> LispObject::from {
+        env.attach_st(number::rem(&x, &y))?
+    }
+}
+
+define_native_fn! {
+    make_mod,
+    native_mod(env, x: number::to_number, y: number::to_number) -> LispObject::from {
+        env.attach_st(number::modulo(&x, &y))?
+    }
+}
+
+define_native_fn! {
+    make_abs,
+    native_abs(_env, x: number::to_number) -> LispObject::from {
+        number::abs(&x)
+    }
+}
+
+define_native_fn! {
+    make_min,
+    native_min(_env, x: number::to_number, ... args: number::to_number) -> LispObject::from {
+        args.iter().fold(x, |acc, x| {
+            if number::compare(x, &acc) == Some(Ordering::Less) { x.clone() } else { acc }
+        })
+    }
+}
+
+define_native_fn! {
+    make_max,
+    native_max(_env, x: number::to_number, ... args: number::to_number) -> LispObject::from {
+        args.iter().fold(x, |acc, x| {
+            if number::compare(x, &acc) == Some(Ordering::Greater) { x.clone() } else { acc }
+        })
+    }
+}
+
+// checks that every pair of adjacent args satisfies predicate
+fn compare_chain(first: &Number, rest: &List<Number>, pred: fn(Ordering) -> bool) -> bool {
+    let mut prev = first;
+    for x in rest.iter() {
+        match number::compare(prev, x) {
+            Some(ord) if pred(ord) => prev = x,
+            _ => return false,
         }
-        res
+    }
+
+    true
+}
+
+define_native_fn! {
+    make_num_eq,
+    native_num_eq(_env, x: number::to_number, ... args: number::to_number) -> identity {
+        native_bool_to_lisp_bool(compare_chain(&x, &args, |ord| ord == Ordering::Equal))
+    }
+}
+
+define_native_fn! {
+    make_num_neq,
+    native_num_neq(_env, x: number::to_number, ... args: number::to_number) -> identity {
+        let all = args.cons(x);
+        let distinct = all.iter().enumerate().all(|(i, x)| {
+            all.iter()
+                .skip(i + 1)
+                .all(|y| number::compare(x, y) != Some(Ordering::Equal))
+        });
+        native_bool_to_lisp_bool(distinct)
     }
 }
 
 define_native_fn! {
     make_lt,
-    native_lt(_env, x: object::to_i64, y: object::to_i64) -> identity {
-        native_bool_to_lisp_bool(x < y)
+    native_lt(_env, x: number::to_number, ... args: number::to_number) -> identity {
+        native_bool_to_lisp_bool(compare_chain(&x, &args, |ord| ord == Ordering::Less))
     }
 }
 
 define_native_fn! {
     make_gt,
-    native_gt(_env, x: object::to_i64, y: object::to_i64) -> identity {
-        native_bool_to_lisp_bool(x > y)
+    native_gt(_env, x: number::to_number, ... args: number::to_number) -> identity {
+        native_bool_to_lisp_bool(compare_chain(&x, &args, |ord| ord == Ordering::Greater))
+    }
+}
+
+define_native_fn! {
+    make_le,
+    native_le(_env, x: number::to_number, ... args: number::to_number) -> identity {
+        native_bool_to_lisp_bool(compare_chain(&x, &args, |ord| ord != Ordering::Greater))
+    }
+}
+
+define_native_fn! {
+    make_ge,
+    native_ge(_env, x: number::to_number, ... args: number::to_number) -> identity {
+        native_bool_to_lisp_bool(compare_chain(&x, &args, |ord| ord != Ordering::Less))
+    }
+}
+
+define_native_fn! {
+    make_number_to_string,
+    native_number_to_string(env, x: number::to_number, [radix: object::to_i64]) -> LispObject::String {
+        let radix = radix.map_or(10, |r| *r);
+        if !(2..=36).contains(&radix) {
+            Err(env.st_err(error::GenericError::new(format!("invalid radix {}", radix))))?
+        }
+
+        let digits = number::to_string_radix(&x, radix as u32).ok_or_else(|| {
+            env.st_err(error::GenericError::new(format!(
+                "cannot print float in radix {}",
+                radix
+            )))
+        })?;
+
+        // prefixed, so that the string is read back as the same number
+        format!("{}{}", number::radix_prefix(radix as u32), digits)
     }
 }
 
@@ -204,6 +359,13 @@ define_native_fn! {
     }
 }
 
+define_native_fn! {
+    make_eq,
+    native_eq(_env, x: identity_converter, y: identity_converter) -> identity {
+        native_bool_to_lisp_bool(x.is_eq(y))
+    }
+}
+
 define_native_fn! {
     make_first,
     native_first(env, list: object::to_list) -> identity {
@@ -244,6 +406,446 @@ define_native_fn! {
     }
 }
 
+define_native_fn! {
+    make_keywordp,
+    native_keywordp(_env, arg: identity_converter) -> identity {
+        let converted = object::to_symbol(arg);
+        native_bool_to_lisp_bool(converted.is_ok_and(|s| s.is_keyword()))
+    }
+}
+
+define_native_fn! {
+    make_charp,
+    native_charp(_env, arg: identity_converter) -> identity {
+        let converted = object::to_char(arg);
+        native_bool_to_lisp_bool(converted.is_ok())
+    }
+}
+
+define_native_fn! {
+    make_char_to_integer,
+    native_char_to_integer(_env, c: object::to_char) -> LispObject::Integer {
+        *c as i64
+    }
+}
+
+define_native_fn! {
+    make_integer_to_char,
+    native_integer_to_char(env, code: object::to_i64) -> LispObject::Char {
+        u32::try_from(*code)
+            .ok()
+            .and_then(std::char::from_u32)
+            .ok_or_else(|| env.st_err(error::GenericError::new(format!(
+                "invalid character code {}",
+                code
+            ))))?
+    }
+}
+
+define_native_fn! {
+    make_string_length,
+    native_string_length(_env, s: object::to_string) -> LispObject::Integer {
+        s.chars().count() as i64
+    }
+}
+
+fn index_out_of_bounds(index: i64, seq: LispObject) -> error::GenericError {
+    error::GenericError::new(format!("index {} is out of bounds of {}", index, seq))
+}
+
+define_native_fn! {
+    make_string_ref,
+    native_string_ref(env, s: object::to_string, index: object::to_i64) -> LispObject::Char {
+        usize::try_from(*index)
+            .ok()
+            .and_then(|i| s.chars().nth(i))
+            .ok_or_else(|| env.st_err(index_out_of_bounds(*index, LispObject::String(s.clone()))))?
+    }
+}
+
+define_native_fn! {
+    make_vectorp,
+    native_vectorp(_env, arg: identity_converter) -> identity {
+        let converted = object::to_vector(arg);
+        native_bool_to_lisp_bool(converted.is_ok())
+    }
+}
+
+define_native_fn! {
+    make_vector,
+    native_vector(_env, ... args: identity_converter) -> LispObject::Vector {
+        args.iter().map(|x| (*x).clone()).collect()
+    }
+}
+
+// checks that index points to an element of vector
+fn vector_index(v: &Vector<LispObject>, index: i64) -> Result<usize, error::GenericError> {
+    usize::try_from(index)
+        .ok()
+        .filter(|i| *i < v.len())
+        .ok_or_else(|| index_out_of_bounds(index, LispObject::Vector(v.clone())))
+}
+
+define_native_fn! {
+    make_vref,
+    native_vref(env, v: object::to_vector, index: object::to_i64) -> identity {
+        let i = env.attach_st(vector_index(v, *index))?;
+        v[i].clone()
+    }
+}
+
+define_native_fn! {
+    make_vset,
+    native_vset(env, v: object::to_vector, index: object::to_i64, x: identity_converter) -> LispObject::Vector {
+        let i = env.attach_st(vector_index(v, *index))?;
+        v.update(i, x.clone())
+    }
+}
+
+define_native_fn! {
+    make_vlength,
+    native_vlength(_env, v: object::to_vector) -> LispObject::Integer {
+        v.len() as i64
+    }
+}
+
+define_native_fn! {
+    make_vpush,
+    native_vpush(_env, v: object::to_vector, x: identity_converter) -> LispObject::Vector {
+        let mut v = v.clone();
+        v.push_back(x.clone());
+        v
+    }
+}
+
+define_native_fn! {
+    make_list_to_vector,
+    native_list_to_vector(_env, list: object::to_list) -> LispObject::Vector {
+        list.iter().cloned().collect()
+    }
+}
+
+define_native_fn! {
+    make_vector_to_list,
+    native_vector_to_list(_env, v: object::to_vector) -> LispObject::List {
+        List::from_rev_iter(v.iter().cloned().collect::<Vec<_>>())
+    }
+}
+
+define_native_fn! {
+    make_mapp,
+    native_mapp(_env, arg: identity_converter) -> identity {
+        let converted = object::to_map(arg);
+        native_bool_to_lisp_bool(converted.is_ok())
+    }
+}
+
+define_native_fn! {
+    make_hash_map,
+    native_hash_map(env, ... args: identity_converter) -> LispObject::Map {
+        if args.len() % 2 != 0 {
+            Err(env.st_err(error::GenericError::new(format!(
+                "odd number of arguments ({}) passed to hash-map",
+                args.len()
+            ))))?
+        }
+
+        let mut map = HashMap::new();
+        let mut args = args.iter();
+
+        while let (Some(k), Some(v)) = (args.next(), args.next()) {
+            map.insert((*k).clone(), (*v).clone());
+        }
+
+        Map(map)
+    }
+}
+
+define_native_fn! {
+    make_get,
+    native_get(env, m: object::to_map, key: identity_converter, ... args: identity_converter) -> identity {
+        if args.len() > 1 {
+            Err(env.st_err(error::ArityError::new(3, args.len() + 2, false, "get")))?
+        }
+
+        match m.0.get(key) {
+            Some(val) => val.clone(),
+            None => args.iter().next().map_or_else(LispObject::nil, |default| (*default).clone()),
+        }
+    }
+}
+
+define_native_fn! {
+    make_assoc,
+    native_assoc(_env, m: object::to_map, key: identity_converter, val: identity_converter) -> LispObject::Map {
+        Map(m.0.update(key.clone(), val.clone()))
+    }
+}
+
+define_native_fn! {
+    make_dissoc,
+    native_dissoc(_env, m: object::to_map, key: identity_converter) -> LispObject::Map {
+        Map(m.0.without(key))
+    }
+}
+
+define_native_fn! {
+    make_containsp,
+    native_containsp(env, coll: identity_converter, key: identity_converter) -> identity {
+        let contains = match coll {
+            LispObject::Map(m) => m.0.contains_key(key),
+            LispObject::Set(s) => s.0.contains(key),
+            x => Err(env.st_err(error::CastError::new(format!("{}", x), "Map or Set")))?,
+        };
+
+        native_bool_to_lisp_bool(contains)
+    }
+}
+
+// keys, vals and map->list list entries ordered by printed keys
+define_native_fn! {
+    make_keys,
+    native_keys(_env, m: object::to_map) -> LispObject::List {
+        List::from_rev_iter(m.sorted_entries().into_iter().map(|(k, _)| k.clone()).collect::<Vec<_>>())
+    }
+}
+
+define_native_fn! {
+    make_vals,
+    native_vals(_env, m: object::to_map) -> LispObject::List {
+        List::from_rev_iter(m.sorted_entries().into_iter().map(|(_, v)| v.clone()).collect::<Vec<_>>())
+    }
+}
+
+define_native_fn! {
+    make_map_to_list,
+    native_map_to_list(_env, m: object::to_map) -> LispObject::List {
+        let entries = m.sorted_entries().into_iter().map(|(k, v)| {
+            LispObject::List(List::empty().cons(v.clone()).cons(k.clone()))
+        });
+
+        List::from_rev_iter(entries.collect::<Vec<_>>())
+    }
+}
+
+define_native_fn! {
+    make_setp,
+    native_setp(_env, arg: identity_converter) -> identity {
+        let converted = object::to_set(arg);
+        native_bool_to_lisp_bool(converted.is_ok())
+    }
+}
+
+define_native_fn! {
+    make_hash_set,
+    native_hash_set(_env, ... items: identity_converter) -> LispObject::Set {
+        Set(items.iter().map(|x| (*x).clone()).collect())
+    }
+}
+
+define_native_fn! {
+    make_list_to_set,
+    native_list_to_set(_env, list: object::to_list) -> LispObject::Set {
+        Set(list.iter().cloned().collect())
+    }
+}
+
+define_native_fn! {
+    make_set_to_list,
+    native_set_to_list(_env, s: object::to_set) -> LispObject::List {
+        List::from_rev_iter(s.sorted_items().into_iter().cloned().collect::<Vec<_>>())
+    }
+}
+
+define_native_fn! {
+    make_union,
+    native_union(_env, ... sets: object::to_set) -> LispObject::Set {
+        Set(HashSet::unions(sets.iter().map(|s| s.0.clone())))
+    }
+}
+
+define_native_fn! {
+    make_intersection,
+    native_intersection(_env, s: object::to_set, ... sets: object::to_set) -> LispObject::Set {
+        Set(sets.iter().fold(s.0.clone(), |acc, other| acc.intersection(other.0.clone())))
+    }
+}
+
+// elements of the first set that are in none of the others
+define_native_fn! {
+    make_difference,
+    native_difference(_env, s: object::to_set, ... sets: object::to_set) -> LispObject::Set {
+        Set(sets.iter().fold(s.0.clone(), |acc, other| acc.relative_complement(other.0.clone())))
+    }
+}
+
+define_native_fn! {
+    make_boxp,
+    native_boxp(_env, arg: identity_converter) -> identity {
+        let converted = object::to_box(arg);
+        native_bool_to_lisp_bool(converted.is_ok())
+    }
+}
+
+define_native_fn! {
+    make_make_box,
+    native_make_box(_env, value: identity_converter) -> LispObject::Box {
+        MutableBox::new(value.clone())
+    }
+}
+
+define_native_fn! {
+    make_box_get,
+    native_box_get(_env, b: object::to_box) -> identity {
+        b.get()
+    }
+}
+
+define_native_fn! {
+    make_box_set,
+    native_box_set(_env, b: object::to_box, value: identity_converter) -> identity {
+        b.set(value.clone());
+        value.clone()
+    }
+}
+
+// sets box to result of calling `f` with its current value and
+// `args`, returns the new value
+define_native_fn! {
+    make_box_swap,
+    native_box_swap(env, b: object::to_box, f: object::to_function, ... args: identity_converter) -> identity {
+        let f_args = std::iter::once(b.get()).chain(args.iter().map(|x| (*x).clone()));
+        let f_args = List::from_rev_iter(f_args.collect::<Vec<_>>());
+
+        let value = eval::call_function_object(env.clone(), f, f_args, false, None)?;
+        b.set(value.clone());
+        value
+    }
+}
+
+define_native_fn! {
+    make_set_dispatch_macro_char,
+    native_set_dispatch_macro_char(env, c: object::to_char, sub_c: object::to_char, f: object::to_function) -> identity {
+        if *c != '#' {
+            Err(env.st_err(error::GenericError::new(format!(
+                "{} is not a dispatch macro char",
+                LispObject::Char(*c)
+            ))))?
+        }
+
+        if lexer::is_builtin_dispatch_char(*sub_c) || sub_c.is_whitespace() {
+            Err(env.st_err(error::GenericError::new(format!(
+                "cannot redefine built-in syntax #{}",
+                sub_c
+            ))))?
+        }
+
+        let mut env = env;
+        env.set_dispatch_macro(*sub_c, f.clone());
+        LispObject::nil()
+    }
+}
+
+fn char_or_nil(c: Option<char>) -> LispObject {
+    c.map_or_else(LispObject::nil, LispObject::Char)
+}
+
+define_native_fn! {
+    make_read_char,
+    native_read_char(env, stream: object::to_stream) -> char_or_nil {
+        env.attach_st_box(stream.input().read_char())?
+    }
+}
+
+define_native_fn! {
+    make_peek_char,
+    native_peek_char(env, stream: object::to_stream) -> char_or_nil {
+        env.attach_st_box(stream.input().peek_char())?
+    }
+}
+
+define_native_fn! {
+    make_open_input_file,
+    native_open_input_file(env, path: object::to_string) -> LispObject::Stream {
+        let file = env.attach_st(fs::File::open(path))?;
+        Stream::new(lexer::Lexer::create_with_file(file, path.clone()))
+    }
+}
+
+// reads next form from stream as data, `None` meaning end of input
+fn read_data(
+    env: &env::Env,
+    stream: &Stream,
+) -> Result<Option<LispObject>, error::ErrorWithStackTrace> {
+    let mut reader = Reader::create_from_stream(stream.clone(), env.clone());
+
+    // input ending inside of a form is reported by reader as located
+    // `EndOfInput`, errors raised by reader macros already have stack
+    // trace
+    reader
+        .read_form()
+        .map_err(|e| match e.downcast::<error::ErrorWithStackTrace>() {
+            Ok(e) => *e,
+            Err(e) => env.st_box_err(e),
+        })
+}
+
+fn eof_value(env: &env::Env, eof_value: Option<&&LispObject>) -> eval::EvalResult {
+    eof_value
+        .map(|v| (*v).clone())
+        .ok_or_else(|| env.st_err(error::EndOfInput::new(false)))
+}
+
+define_native_fn! {
+    make_read,
+    native_read(env, stream: object::to_stream, ... eof: identity_converter) -> identity {
+        if eof.len() > 1 {
+            Err(env.st_err(error::ArityError::new(2, eof.len() + 1, false, "read")))?
+        }
+
+        match read_data(&env, stream)? {
+            Some(form) => form,
+            None => eof_value(&env, eof.first())?,
+        }
+    }
+}
+
+define_native_fn! {
+    make_read_from_string,
+    native_read_from_string(env, s: object::to_string, ... args: identity_converter) -> identity {
+        if args.len() > 2 {
+            Err(env.st_err(error::ArityError::new(3, args.len() + 1, false, "read-from-string")))?
+        }
+
+        let mut args = args.iter();
+        let start = match args.next() {
+            Some(start) => *env.attach_st(object::to_i64(start))?,
+            None => 0,
+        };
+
+        let len = s.chars().count();
+        let start = usize::try_from(start)
+            .ok()
+            .filter(|start| *start <= len)
+            .ok_or_else(|| env.st_err(error::GenericError::new(format!(
+                "index {} is out of bounds of string {}",
+                start,
+                LispObject::String(s.clone())
+            ))))?;
+
+        let input = io::Cursor::new(s.chars().skip(start).collect::<String>());
+        let stream = Stream::new(lexer::Lexer::create_with_file(input, "<string>"));
+
+        let form = match read_data(&env, &stream)? {
+            Some(form) => form,
+            None => eof_value(&env, args.next())?,
+        };
+        let end = (start + stream.input().offset()) as i64;
+
+        LispObject::List(List::empty().cons(LispObject::Integer(end)).cons(form))
+    }
+}
+
 define_native_fn! {
     make_macroexpand,
     native_macroexpand(env, arg: identity_converter) -> identity {
@@ -293,23 +895,85 @@ pub fn prepare_natives(env: &mut env::Env) {
     save("first", make_first);
     save("rest", make_rest);
     save("equal", make_equal);
+    save("eq", make_eq);
+    // with numbers compared by value by `eq`, `eql` is the same
+    save("eql", make_eq);
     save("apply", make_apply);
 
     save("+", make_add);
     save("-", make_sub);
     save("*", make_mul);
+    save("/", make_div);
+    save("quot", make_quot);
+    save("rem", make_rem);
+    save("mod", make_mod);
+    save("abs", make_abs);
+    save("min", make_min);
+    save("max", make_max);
+
+    save("=", make_num_eq);
+    save("/=", make_num_neq);
     save("<", make_lt);
     save(">", make_gt);
+    save("<=", make_le);
+    save(">=", make_ge);
+    save("number->string", make_number_to_string);
 
     save("listp", make_listp);
     save("emptyp", make_emptyp);
     save("symbolp", make_symbolp);
+    save("keywordp", make_keywordp);
+    save("charp", make_charp);
+
+    save("char->integer", make_char_to_integer);
+    save("integer->char", make_integer_to_char);
+    save("string-length", make_string_length);
+    save("string-ref", make_string_ref);
+
+    save("vectorp", make_vectorp);
+    save("vector", make_vector);
+    save("vref", make_vref);
+    save("vset", make_vset);
+    save("vlength", make_vlength);
+    save("vpush", make_vpush);
+    save("list->vector", make_list_to_vector);
+    save("vector->list", make_vector_to_list);
+
+    save("mapp", make_mapp);
+    save("hash-map", make_hash_map);
+    save("get", make_get);
+    save("assoc", make_assoc);
+    save("dissoc", make_dissoc);
+    save("contains?", make_containsp);
+    save("keys", make_keys);
+    save("vals", make_vals);
+    save("map->list", make_map_to_list);
+
+    save("setp", make_setp);
+    save("hash-set", make_hash_set);
+    save("list->set", make_list_to_set);
+    save("set->list", make_set_to_list);
+    save("union", make_union);
+    save("intersection", make_intersection);
+    save("difference", make_difference);
+
+    save("boxp", make_boxp);
+    save("make-box", make_make_box);
+    save("box-get", make_box_get);
+    save("box-set!", make_box_set);
+    save("box-swap!", make_box_swap);
 
     save("print", make_print);
     save("println", make_println);
     save("stdout-write", make_stdout_write);
 
     save("macroexpand-1", make_macroexpand);
+    save("set-dispatch-macro-char", make_set_dispatch_macro_char);
+    save("read-char", make_read_char);
+    save("peek-char", make_peek_char);
+    save("open-input-file", make_open_input_file);
+    save("read", make_read);
+    save("read-from-string", make_read_from_string);
 
     save("error", make_raise_error);
     save("symbol-function", make_symbol_function);
@@ -378,6 +1042,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_eq() {
+        let ctx = ctx();
+        assert_ok!(ctx, "(eq (quote foo) (quote foo))", "t");
+        assert_ok!(ctx, "(eq (quote foo) (quote bar))", "nil");
+        assert_ok!(ctx, "(eq nil ())", "t");
+        assert_ok!(ctx, "(eq t t)", "t");
+        assert_ok!(ctx, "(let ((x (quote (1 2)))) (eq x x))", "t");
+        assert_ok!(ctx, "(let ((x (quote (1 2)))) (eq (rest x) (rest x)))", "t");
+        assert_ok!(ctx, "(eq (quote (1 2)) (quote (1 2)))", "nil");
+        assert_ok!(ctx, "(let ((x [1 2 3])) (eq x x))", "t");
+        assert_ok!(ctx, "(let ((x [1])) (eq x x))", "t");
+        assert_ok!(ctx, "(eq [1 2 3] [1 2 3])", "nil");
+        assert_ok!(ctx, "(eq [] (vector))", "t");
+        assert_ok!(ctx, "(let ((x {:a 1})) (eq x x))", "t");
+        assert_ok!(ctx, "(eq {:a 1} {:a 1})", "nil");
+        assert_ok!(ctx, "(let ((x #{1})) (eq x x))", "t");
+        assert_ok!(ctx, "(eq #{1} #{1})", "nil");
+        assert_ok!(ctx, "(let ((b (make-box 1))) (eq b b))", "t");
+        assert_ok!(ctx, "(eq (make-box 1) (make-box 1))", "nil");
+        assert_ok!(
+            ctx,
+            "(eq (symbol-function (quote cons)) (symbol-function (quote cons)))",
+            "t"
+        );
+        assert_ok!(ctx, "(let ((f (lambda (x) x))) (eq f f))", "t");
+        assert_ok!(ctx, "(eq (lambda (x) x) (lambda (x) x))", "nil");
+        assert_ok!(ctx, "(eq 1 1)", "t");
+        assert_ok!(ctx, "(eq 1 2)", "nil");
+        assert_ok!(ctx, "(let ((x 1)) (eq x x))", "t");
+        assert_ok!(ctx, "(eq #\\a #\\a)", "t");
+        assert_ok!(ctx, "(eq #\\a #\\b)", "nil");
+        assert_ok!(ctx, "(eq 1 #\\a)", "nil");
+        assert_ok!(ctx, "(eq 1 1.0)", "nil");
+        assert_ok!(ctx, "(eq 1.5 1.5)", "t");
+        assert_ok!(ctx, "(eq 0.0 -0.0)", "nil");
+        assert_ok!(ctx, "(eq 99999999999999999999 99999999999999999999)", "t");
+        assert_ok!(ctx, "(eq \"a\" \"a\")", "t");
+        assert_ok!(ctx, "(eq \"a\" \"b\")", "nil");
+
+        for x in &[
+            "1",
+            "99999999999999999999",
+            "1.5",
+            "+nan.0",
+            "#\\a",
+            "\"a\"",
+            "(quote foo)",
+            ":foo",
+            "t",
+            "nil",
+            "(quote (1 2))",
+            "[1]",
+            "{:a 1}",
+            "#{1}",
+            "(make-box 1)",
+            "(lambda (x) x)",
+            "(symbol-function (quote cons))",
+        ] {
+            for f in &["eq", "eql"] {
+                assert_ok!(ctx, format!("(let ((x {})) ({} x x))", x, f), "t");
+            }
+        }
+    }
+
+    #[test]
+    fn test_eql() {
+        let ctx = ctx();
+        assert_ok!(ctx, "(eql 1 1)", "t");
+        assert_ok!(ctx, "(eql 1 1.0)", "nil");
+        assert_ok!(ctx, "(eql 1.5 1.5)", "t");
+        assert_ok!(ctx, "(eql 99999999999999999999 99999999999999999999)", "t");
+        assert_ok!(ctx, "(eql #\\a #\\a)", "t");
+        assert_ok!(ctx, "(eql #\\a #\\b)", "nil");
+        assert_ok!(ctx, "(eql (quote foo) (quote foo))", "t");
+        assert_ok!(ctx, "(eql +nan.0 +nan.0)", "t");
+        assert_ok!(ctx, "(eql \"a\" \"a\")", "t");
+        assert_ok!(ctx, "(eql (quote (1)) (quote (1)))", "nil");
+        assert_ok!(ctx, "(let ((x (quote (1)))) (eql x x))", "t");
+    }
+
     #[test]
     fn test_apply() {
         let ctx = ctx();
@@ -418,6 +1163,23 @@ mod tests {
         assert_ok!(ctx, "(+ 1 2 3 4 5)", "15");
     }
 
+    #[test]
+    fn test_float_arithmetic() {
+        let ctx = ctx();
+
+        assert_ok!(ctx, "(+ 1 0.5)", "1.5");
+        assert_ok!(ctx, "(+ 1.5 1.5)", "3.0");
+        assert_ok!(ctx, "(+ (- 1.5) 3)", "1.5");
+        assert_ok!(ctx, "(- 3 0.5 0.5)", "2.0");
+        assert_ok!(ctx, "(* 2 1.5 2)", "6.0");
+        assert_ok!(ctx, "(< 1 1.5)", "t");
+        assert_ok!(ctx, "(> 1 1.5)", "nil");
+        assert_ok!(ctx, "(equal 1 1.0)", "nil");
+        assert_ok!(ctx, "(equal 1.0 1.0)", "t");
+
+        assert_err!(ctx, "(+ 1.5 (quote x))", error::CastError);
+    }
+
     #[test]
     fn test_sub() {
         let ctx = ctx();
@@ -427,6 +1189,10 @@ mod tests {
         assert_ok!(ctx, "(+ (- 1) 1)", "0");
         assert_ok!(ctx, "(+ 1 (- 1 2))", "0");
         assert_ok!(ctx, "(+ 13 (- 1 2 3 4 5))", "0");
+        assert_ok!(ctx, "(- 5)", "-5");
+        assert_ok!(ctx, "(- -5 +3)", "-8");
+        assert_ok!(ctx, "(- -1.5)", "1.5");
+        assert_ok!(ctx, "(- -9223372036854775807 2)", "-9223372036854775809");
     }
 
     #[test]
@@ -443,22 +1209,178 @@ mod tests {
     fn test_lt() {
         let ctx = ctx();
         assert_err!(ctx, "(<)", error::ArityError);
-        assert_err!(ctx, "(< 1)", error::ArityError);
         assert_err!(ctx, "(< 1 (quote x))", error::CastError);
 
+        assert_ok!(ctx, "(< 1)", "t");
         assert_ok!(ctx, "(< 1 2)", "t");
         assert_ok!(ctx, "(< 2 1)", "nil");
+        assert_ok!(ctx, "(< 1 2 3)", "t");
+        assert_ok!(ctx, "(< 1 3 2)", "nil");
+        assert_ok!(ctx, "(< 1 1)", "nil");
     }
 
     #[test]
     fn test_gt() {
         let ctx = ctx();
         assert_err!(ctx, "(>)", error::ArityError);
-        assert_err!(ctx, "(> 1)", error::ArityError);
         assert_err!(ctx, "(> 1 (quote x))", error::CastError);
 
+        assert_ok!(ctx, "(> 1)", "t");
         assert_ok!(ctx, "(> 1 2)", "nil");
         assert_ok!(ctx, "(> 2 1)", "t");
+        assert_ok!(ctx, "(> 3 2 1)", "t");
+        assert_ok!(ctx, "(> 3 1 2)", "nil");
+    }
+
+    #[test]
+    fn test_le_ge() {
+        let ctx = ctx();
+        assert_err!(ctx, "(<=)", error::ArityError);
+        assert_err!(ctx, "(>=)", error::ArityError);
+
+        assert_ok!(ctx, "(<= 1 1 2)", "t");
+        assert_ok!(ctx, "(<= 1 2 1)", "nil");
+        assert_ok!(ctx, "(>= 2 2 1.5)", "t");
+        assert_ok!(ctx, "(>= 1 2)", "nil");
+    }
+
+    #[test]
+    fn test_num_eq() {
+        let ctx = ctx();
+        assert_err!(ctx, "(=)", error::ArityError);
+        assert_err!(ctx, "(/=)", error::ArityError);
+        assert_err!(ctx, "(= 1 (quote x))", error::CastError);
+
+        assert_ok!(ctx, "(= 1)", "t");
+        assert_ok!(ctx, "(= 1 1 1)", "t");
+        assert_ok!(ctx, "(= 1 1.0)", "t");
+        assert_ok!(ctx, "(= 1 1 2)", "nil");
+
+        assert_ok!(ctx, "(/= 1)", "t");
+        assert_ok!(ctx, "(/= 1 2 3)", "t");
+        assert_ok!(ctx, "(/= 1 2 1)", "nil");
+        assert_ok!(ctx, "(/= 1 1.0)", "nil");
+    }
+
+    #[test]
+    fn test_div() {
+        let ctx = ctx();
+        assert_err!(ctx, "(/)", error::ArityError);
+        assert_err!(ctx, "(/ 0)", error::DivisionByZero);
+        assert_err!(ctx, "(/ 1 0)", error::DivisionByZero);
+        assert_err!(ctx, "(/ 1 2 0)", error::DivisionByZero);
+        assert_err!(ctx, "(/ 1.5 0.0)", error::DivisionByZero);
+
+        assert_ok!(ctx, "(/ 2)", "0.5");
+        assert_ok!(ctx, "(/ 6 3)", "2");
+        assert_ok!(ctx, "(/ 12 2 3)", "2");
+        assert_ok!(ctx, "(/ 7 2)", "3.5");
+        assert_ok!(ctx, "(/ 1.5 0.5)", "3.0");
+    }
+
+    #[test]
+    fn test_quot_rem_mod() {
+        let ctx = ctx();
+        assert_err!(ctx, "(quot 1)", error::ArityError);
+        assert_err!(ctx, "(rem 1 2 3)", error::ArityError);
+        assert_err!(ctx, "(quot 1 0)", error::DivisionByZero);
+        assert_err!(ctx, "(rem 1 0)", error::DivisionByZero);
+        assert_err!(ctx, "(mod 1 0)", error::DivisionByZero);
+
+        assert_ok!(ctx, "(quot 7 2)", "3");
+        assert_ok!(ctx, "(= (quot (- 7) 2) (- 3))", "t");
+        assert_ok!(ctx, "(rem 7 3)", "1");
+        assert_ok!(ctx, "(= (rem (- 7) 3) (- 1))", "t");
+        assert_ok!(ctx, "(mod (- 7) 3)", "2");
+        assert_ok!(ctx, "(= (mod 7 (- 3)) (- 2))", "t");
+    }
+
+    #[test]
+    fn test_abs_min_max() {
+        let ctx = ctx();
+        assert_err!(ctx, "(abs)", error::ArityError);
+        assert_err!(ctx, "(min)", error::ArityError);
+        assert_err!(ctx, "(max)", error::ArityError);
+
+        assert_ok!(ctx, "(abs (- 5))", "5");
+        assert_ok!(ctx, "(abs 2.5)", "2.5");
+        assert_ok!(ctx, "(min 3)", "3");
+        assert_ok!(ctx, "(min 3 1 2)", "1");
+        assert_ok!(ctx, "(min 3 1.5 2)", "1.5");
+        assert_ok!(ctx, "(max 3 1 2)", "3");
+        assert_ok!(ctx, "(max 1 2.5 2)", "2.5");
+    }
+
+    #[test]
+    fn test_bigint_arithmetic() {
+        let ctx = ctx();
+        assert_ok!(ctx, "(* 99999999999 99999999999)", "9999999999800000000001");
+        assert_ok!(ctx, "(+ 9223372036854775807 1)", "9223372036854775808");
+        assert_ok!(
+            ctx,
+            "(= (- (- 9223372036854775807) 2) (- 9223372036854775809))",
+            "t"
+        );
+        assert_ok!(ctx, "(- 9223372036854775808 1)", "9223372036854775807");
+        assert_ok!(
+            ctx,
+            "(equal (- 9223372036854775808 1) 9223372036854775807)",
+            "t"
+        );
+        assert_ok!(
+            ctx,
+            "(abs (- (- 9223372036854775807) 1))",
+            "9223372036854775808"
+        );
+        assert_ok!(
+            ctx,
+            "(quot 100000000000000000000 3)",
+            "33333333333333333333"
+        );
+        assert_ok!(ctx, "(rem 100000000000000000000 3)", "1");
+        assert_ok!(ctx, "(mod (- 100000000000000000000) 3)", "2");
+        assert_ok!(ctx, "(/ 100000000000000000000 4)", "25000000000000000000");
+        assert_ok!(ctx, "(/ 100000000000000000000 3)", "33333333333333330000.0");
+        assert_ok!(ctx, "(+ 100000000000000000000 0.5)", "1e20");
+        assert_ok!(ctx, "(< 1 100000000000000000000 1e30)", "t");
+        assert_ok!(ctx, "(= 100000000000000000000 100000000000000000000)", "t");
+        assert_ok!(
+            ctx,
+            "(max 1 100000000000000000000)",
+            "100000000000000000000"
+        );
+        assert_err!(ctx, "(quot 100000000000000000000 0)", error::DivisionByZero);
+    }
+
+    #[test]
+    fn test_number_to_string() {
+        let ctx = ctx();
+        assert_err!(ctx, "(number->string)", error::ArityError);
+        assert_err!(ctx, "(number->string 1 2 3)", error::ArityError);
+        assert_err!(ctx, "(number->string 1 2 (quote x))", error::ArityError);
+        assert_err!(ctx, "(number->string \"1\")", error::CastError);
+        assert_err!(ctx, "(number->string 1 37)", error::GenericError);
+        assert_err!(ctx, "(number->string 1.5 16)", error::GenericError);
+
+        assert_ok!(ctx, "(number->string 42)", "\"42\"");
+        assert_ok!(ctx, "(number->string 42 10)", "\"42\"");
+        assert_ok!(ctx, "(number->string #x1F 16)", "\"#x1f\"");
+        assert_ok!(ctx, "(number->string -10 2)", "\"#b-1010\"");
+        assert_ok!(ctx, "(number->string 15 8)", "\"#o17\"");
+        assert_ok!(ctx, "(number->string 1295 36)", "\"#36rzz\"");
+        assert_ok!(ctx, "(number->string 1.5)", "\"1.5\"");
+        assert_ok!(ctx, "(number->string 1.5 10)", "\"1.5\"");
+        assert_ok!(
+            ctx,
+            "(number->string #x1_0000_0000_0000_0000 16)",
+            "\"#x10000000000000000\""
+        );
+
+        let big = "-123456789012345678901234567890";
+        for (x, radix) in &[("-255", 16), ("10", 2), ("8", 8), ("1295", 36), (big, 7)] {
+            let printed = ctx.ok_eval(format!("(number->string {} {})", x, radix));
+            assert_eq!(read(object::to_string(&printed).unwrap().clone()), read(*x));
+        }
     }
 
     #[test]
@@ -495,6 +1417,259 @@ mod tests {
         assert_ok!(ctx, "(symbolp t)", "nil");
     }
 
+    #[test]
+    fn test_keywordp() {
+        let ctx = ctx();
+        assert_err!(ctx, "(keywordp)", error::ArityError);
+
+        assert_ok!(ctx, "(keywordp :foo)", "t");
+        assert_ok!(ctx, "(keywordp (quote :foo))", "t");
+        assert_ok!(ctx, "(symbolp :foo)", "t");
+        assert_ok!(ctx, "(keywordp (quote foo))", "nil");
+        assert_ok!(ctx, "(keywordp \":foo\")", "nil");
+        assert_ok!(ctx, "(keywordp nil)", "nil");
+    }
+
+    #[test]
+    fn test_chars() {
+        let ctx = ctx();
+        assert_err!(ctx, "(charp)", error::ArityError);
+        assert_err!(ctx, "(char->integer 97)", error::CastError);
+        assert_err!(ctx, "(integer->char -1)", error::GenericError);
+        assert_err!(ctx, "(integer->char 55296)", error::GenericError);
+
+        assert_ok!(ctx, "(charp #\\a)", "t");
+        assert_ok!(ctx, "(charp \"a\")", "nil");
+        assert_ok!(ctx, "(char->integer #\\a)", "97");
+        assert_ok!(ctx, "(char->integer #\\newline)", "10");
+        assert_ok!(ctx, "(integer->char 955)", "#\\λ");
+        assert_ok!(ctx, "(equal #\\a #\\a)", "t");
+        assert_ok!(ctx, "(equal #\\a \"a\")", "nil");
+    }
+
+    #[test]
+    fn test_string_ref() {
+        let ctx = ctx();
+        assert_err!(ctx, "(string-ref \"abc\" 3)", error::GenericError);
+        assert_err!(ctx, "(string-ref \"abc\" -1)", error::GenericError);
+        assert_err!(ctx, "(string-ref (quote abc) 0)", error::CastError);
+
+        assert_ok!(ctx, "(string-ref \"abc\" 0)", "#\\a");
+        assert_ok!(ctx, "(string-ref \"привет\" 5)", "#\\т");
+        assert_ok!(ctx, "(string-ref \"a b\" 1)", "#\\space");
+        assert_ok!(ctx, "(string-length \"\")", "0");
+        assert_ok!(ctx, "(string-length \"привет\")", "6");
+    }
+
+    #[test]
+    fn test_vectors() {
+        let ctx = ctx();
+        assert_ok!(ctx, "[1 (+ 1 2) x]", "[1 (+ 1 2) x]");
+        assert_ok!(ctx, "(vector 1 (+ 1 2))", "[1 3]");
+        assert_ok!(ctx, "(vector)", "[]");
+        assert_ok!(ctx, "(vref [a b c] 1)", "b");
+        assert_ok!(ctx, "(vset [a b c] 1 (quote x))", "[a x c]");
+        assert_ok!(ctx, "(vpush [a b] (quote c))", "[a b c]");
+        assert_ok!(ctx, "(vlength [a b c])", "3");
+        assert_ok!(ctx, "(list->vector (cons 1 (cons 2 nil)))", "[1 2]");
+        assert_ok!(ctx, "(vector->list [1 2])", "(1 2)");
+        assert_ok!(ctx, "(vector->list [])", "()");
+        assert_ok!(ctx, "(vectorp #(1))", "t");
+        assert_ok!(ctx, "(vectorp (quote (1)))", "nil");
+        assert_ok!(ctx, "(equal [1 [2]] #(1 [2]))", "t");
+        assert_ok!(ctx, "(equal [1 2] (cons 1 (cons 2 nil)))", "nil");
+
+        assert_err!(ctx, "(vref [a b c] 3)", error::GenericError);
+        assert_err!(ctx, "(vref [a b c] -1)", error::GenericError);
+        assert_err!(ctx, "(vset [] 0 1)", error::GenericError);
+        assert_err!(ctx, "(vref (quote (a)) 0)", error::CastError);
+    }
+
+    #[test]
+    fn test_maps() {
+        let ctx = ctx();
+        assert_ok!(ctx, "{:a (+ 1 2)}", "{:a (+ 1 2)}");
+        assert_ok!(ctx, "(hash-map :a (+ 1 2) :b 3)", "{:b 3 :a 3}");
+        assert_ok!(ctx, "(hash-map)", "{}");
+        assert_ok!(ctx, "(mapp {})", "t");
+        assert_ok!(ctx, "(mapp [])", "nil");
+        assert_ok!(ctx, "(get {:a 1} :a)", "1");
+        assert_ok!(ctx, "(get {:a 1} :b)", "nil");
+        assert_ok!(ctx, "(get {:a 1} :b 2)", "2");
+        assert_ok!(ctx, "(get {[1 2] 3} #(1 2))", "3");
+        assert_ok!(ctx, "(get {{:a 1 :b 2} x} {:b 2 :a 1})", "x");
+        assert_ok!(ctx, "(assoc {:a 1} :b 2)", "{:a 1 :b 2}");
+        assert_ok!(ctx, "(assoc {:a 1} :a 2)", "{:a 2}");
+        assert_ok!(ctx, "(dissoc {:a 1 :b 2} :a)", "{:b 2}");
+        assert_ok!(ctx, "(dissoc {:a 1} :b)", "{:a 1}");
+        assert_ok!(ctx, "(contains? {:a nil} :a)", "t");
+        assert_ok!(ctx, "(contains? {:a 1} 1)", "nil");
+        assert_ok!(ctx, "(keys {:a 1})", "(:a)");
+        assert_ok!(ctx, "(vals {:a 1})", "(1)");
+        assert_ok!(ctx, "(keys {})", "()");
+        assert_ok!(ctx, "(keys {:b 2 :c 3 :a 1})", "(:a :b :c)");
+        assert_ok!(ctx, "(vals {:b 2 :c 3 :a 1})", "(1 2 3)");
+        assert_ok!(ctx, "(map->list {:b 2 :a 1})", "((:a 1) (:b 2))");
+        assert_ok!(ctx, "(map->list {:a 1})", "((:a 1))");
+        assert_ok!(ctx, "(equal {:a 1 :b 2} (assoc {:b 2} :a 1))", "t");
+        assert_ok!(ctx, "(equal {:a 1} {:a 2})", "nil");
+
+        assert_err!(ctx, "(hash-map :a)", error::GenericError);
+        assert_err!(ctx, "(get {} :a 1 2)", error::ArityError);
+        assert_err!(ctx, "(get [] 0)", error::CastError);
+    }
+
+    #[test]
+    fn test_sets() {
+        let ctx = ctx();
+        assert_ok!(ctx, "#{(+ 1 2)}", "#{(+ 1 2)}");
+        assert_ok!(ctx, "(hash-set 1 (+ 1 2) 1)", "#{3 1}");
+        assert_ok!(ctx, "(hash-set)", "#{}");
+        assert_ok!(ctx, "(setp #{})", "t");
+        assert_ok!(ctx, "(setp {})", "nil");
+        assert_ok!(ctx, "(list->set (cons 1 (cons 2 (cons 1 nil))))", "#{1 2}");
+        assert_ok!(ctx, "(set->list #{a})", "(a)");
+        assert_ok!(ctx, "(set->list #{c a b})", "(a b c)");
+        assert_ok!(ctx, "(contains? #{1 [2]} [2])", "t");
+        assert_ok!(ctx, "(contains? #{1} 2)", "nil");
+        assert_ok!(ctx, "(union #{1 2} #{2 3} #{4})", "#{1 2 3 4}");
+        assert_ok!(ctx, "(union)", "#{}");
+        assert_ok!(ctx, "(intersection #{1 2 3} #{2 3 4} #{3 2})", "#{2 3}");
+        assert_ok!(ctx, "(intersection #{1})", "#{1}");
+        assert_ok!(ctx, "(difference #{1 2 3} #{2} #{3 4})", "#{1}");
+        assert_ok!(ctx, "(equal #{1 2} #{2 1})", "t");
+        assert_ok!(ctx, "(contains? #{#{1 2}} #{2 1})", "t");
+
+        assert_err!(ctx, "(union #{1} [2])", error::CastError);
+        assert_err!(ctx, "(intersection)", error::ArityError);
+        assert_err!(ctx, "(contains? [1] 0)", error::CastError);
+    }
+
+    #[test]
+    fn test_boxes() {
+        let ctx = ctx();
+        assert_ok!(ctx, "(box-get (make-box (+ 1 2)))", "3");
+        assert_ok!(ctx, "(boxp (make-box 1))", "t");
+        assert_ok!(ctx, "(boxp 1)", "nil");
+        assert_ok!(
+            ctx,
+            "(let ((b (make-box 1))) (cons (box-set! b 2) (cons (box-get b) nil)))",
+            "(2 2)"
+        );
+        assert_ok!(
+            ctx,
+            "(let ((b (make-box 1)))
+               (box-swap! b (symbol-function (quote +)) 2 3)
+               (box-swap! b (lambda (x) (* x 10))))",
+            "60"
+        );
+        assert_ok!(ctx, "(let ((b (make-box 1))) (equal b b))", "t");
+        assert_ok!(ctx, "(equal (make-box 1) (make-box 1))", "nil");
+        assert_ok!(
+            ctx,
+            "(let ((b (make-box 1))) (box-set! b 2) (contains? (hash-set b) b))",
+            "t"
+        );
+
+        assert_err!(ctx, "(box-get 1)", error::CastError);
+        assert_err!(
+            ctx,
+            "(box-swap! (make-box 1) (symbol-function (quote +)) x)",
+            error::UndefinedSymbol
+        );
+        assert_err!(
+            ctx,
+            "(box-swap! (make-box (quote a)) (symbol-function (quote +)))",
+            error::CastError
+        );
+    }
+
+    #[test]
+    fn test_dispatch_macros() {
+        let ctx = ctx();
+        assert_err!(
+            ctx,
+            "(set-dispatch-macro-char #\\$ #\\! (lambda (s c)))",
+            error::GenericError
+        );
+        assert_err!(
+            ctx,
+            "(set-dispatch-macro-char #\\# #\\x (lambda (s c)))",
+            error::GenericError
+        );
+        assert_err!(
+            ctx,
+            "(set-dispatch-macro-char #\\# #\\! 1)",
+            error::CastError
+        );
+        assert_err!(ctx, "(read-char 1)", error::CastError);
+
+        let set_macro = "(set-dispatch-macro-char #\\# #\\!
+                           (lambda (s c)
+                             (cons (quote quote)
+                                   (cons (cons c (cons (read-char s) (cons (peek-char s) nil))) nil))))";
+
+        assert_ok!(
+            ctx,
+            format!("{} (first (cons #!a nil))", set_macro),
+            "(#\\! #\\a #\\space)"
+        );
+        assert_ok!(ctx, format!("{} #!a", set_macro), "(#\\! #\\a nil)");
+    }
+
+    #[test]
+    fn test_read_from_string() {
+        let ctx = ctx();
+        assert_err!(ctx, "(read-from-string)", error::ArityError);
+        assert_err!(ctx, "(read-from-string \"a\" 0 nil 1)", error::ArityError);
+        assert_err!(ctx, "(read-from-string \"a\" 2)", error::GenericError);
+        assert_err!(ctx, "(read-from-string \"  \")", error::EndOfInput);
+        assert_err!(ctx, "(read-from-string \"(a b\")", error::EndOfInput);
+        assert_err!(ctx, "(read-from-string \"(a b\" 0 nil)", error::EndOfInput);
+        assert_err!(ctx, "(read-from-string \")\")", error::SyntaxError);
+
+        assert_ok!(ctx, "(read-from-string \"(a (b 1)) c\")", "((a (b 1)) 9)");
+        assert_ok!(ctx, "(read-from-string \"(a (b 1)) c\" 9)", "(c 11)");
+        assert_ok!(ctx, "(read-from-string \"'x\")", "('x 2)");
+        assert_ok!(ctx, "(read-from-string \"λ 1\" 1)", "(1 3)");
+        assert_ok!(
+            ctx,
+            "(read-from-string \"(+ 1 2) \" 7 (quote eof))",
+            "(eof 8)"
+        );
+    }
+
+    #[test]
+    fn test_read() {
+        let path = std::env::temp_dir().join(format!("unlisp-read-test-{}", std::process::id()));
+        std::fs::write(&path, "(a 1) \"b\"\n#\\c").unwrap();
+        let lisp_path = object::LispObject::String(path.to_str().unwrap().to_string());
+
+        let ctx = ctx();
+        assert_err!(ctx, "(read)", error::ArityError);
+        assert_err!(ctx, "(read 1)", error::CastError);
+        assert_err!(
+            ctx,
+            "(open-input-file \"/nonexistent/file\")",
+            std::io::Error
+        );
+
+        let read_all = format!(
+            "(set-fn read-all (lambda (s) (let ((x (read s nil))) (if x (cons x (read-all s)) nil))))
+             (read-all (open-input-file {}))",
+            lisp_path
+        );
+        assert_ok!(ctx, read_all, "((a 1) \"b\" #\\c)");
+
+        let read_past_end = format!(
+            "(let ((s (open-input-file {}))) (read s) (read s) (read s) (read s))",
+            lisp_path
+        );
+        assert_err!(ctx, read_past_end, error::EndOfInput);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
     #[test]
     fn test_macroexpand_1() {
         let ctx = ctx();
@@ -0,0 +1,319 @@
+use crate::bigint::BigInt;
+use crate::error;
+use crate::object;
+use crate::object::LispObject;
+use std::cmp::Ordering;
+
+/// Numeric value extracted from `LispObject`. Operations on mixed
+/// integer and float arguments produce floats, integer operations
+/// that overflow i64 produce big integers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Number {
+    Integer(i64),
+    BigInteger(BigInt),
+    Float(f64),
+}
+
+impl Number {
+    fn to_f64(&self) -> f64 {
+        match self {
+            Number::Integer(i) => *i as f64,
+            Number::BigInteger(b) => b.to_f64(),
+            Number::Float(x) => *x,
+        }
+    }
+}
+
+// demotes big integers which fit into i64, so every integer
+// has a single representation
+fn from_bigint(b: BigInt) -> Number {
+    match b.to_i64() {
+        Some(i) => Number::Integer(i),
+        None => Number::BigInteger(b),
+    }
+}
+
+impl From<Number> for LispObject {
+    fn from(n: Number) -> Self {
+        match n {
+            Number::Integer(i) => LispObject::Integer(i),
+            Number::BigInteger(b) => LispObject::BigInteger(b),
+            Number::Float(x) => LispObject::Float(object::Float(x)),
+        }
+    }
+}
+
+pub fn to_number(arg: &LispObject) -> Result<Number, error::CastError> {
+    match arg {
+        LispObject::Integer(i) => Ok(Number::Integer(*i)),
+        LispObject::BigInteger(b) => Ok(Number::BigInteger(b.clone())),
+        LispObject::Float(x) => Ok(Number::Float(x.0)),
+        x => Err(error::CastError::new(format!("{}", x), "number")),
+    }
+}
+
+// arguments of binary operation converted to a common type
+enum Operands {
+    Integers(i64, i64),
+    BigIntegers(BigInt, BigInt),
+    Floats(f64, f64),
+}
+
+fn operands(x: &Number, y: &Number) -> Operands {
+    match (x, y) {
+        (Number::Integer(x), Number::Integer(y)) => Operands::Integers(*x, *y),
+        (Number::Float(_), _) | (_, Number::Float(_)) => Operands::Floats(x.to_f64(), y.to_f64()),
+        (Number::BigInteger(x), Number::BigInteger(y)) => {
+            Operands::BigIntegers(x.clone(), y.clone())
+        }
+        (Number::BigInteger(x), Number::Integer(y)) => {
+            Operands::BigIntegers(x.clone(), BigInt::from(*y))
+        }
+        (Number::Integer(x), Number::BigInteger(y)) => {
+            Operands::BigIntegers(BigInt::from(*x), y.clone())
+        }
+    }
+}
+
+fn binary_op(
+    x: &Number,
+    y: &Number,
+    int_op: fn(i64, i64) -> Option<i64>,
+    big_op: fn(&BigInt, &BigInt) -> BigInt,
+    float_op: fn(f64, f64) -> f64,
+) -> Number {
+    match operands(x, y) {
+        Operands::Integers(x, y) => match int_op(x, y) {
+            Some(res) => Number::Integer(res),
+            None => from_bigint(big_op(&BigInt::from(x), &BigInt::from(y))),
+        },
+        Operands::BigIntegers(x, y) => from_bigint(big_op(&x, &y)),
+        Operands::Floats(x, y) => Number::Float(float_op(x, y)),
+    }
+}
+
+pub fn add(x: &Number, y: &Number) -> Number {
+    binary_op(x, y, i64::checked_add, BigInt::add, |x, y| x + y)
+}
+
+pub fn sub(x: &Number, y: &Number) -> Number {
+    binary_op(x, y, i64::checked_sub, BigInt::sub, |x, y| x - y)
+}
+
+pub fn mul(x: &Number, y: &Number) -> Number {
+    binary_op(x, y, i64::checked_mul, BigInt::mul, |x, y| x * y)
+}
+
+pub fn neg(x: &Number) -> Number {
+    match x {
+        Number::Integer(i) => match i.checked_neg() {
+            Some(res) => Number::Integer(res),
+            None => from_bigint(BigInt::from(*i).neg()),
+        },
+        Number::BigInteger(b) => from_bigint(b.neg()),
+        Number::Float(x) => Number::Float(-x),
+    }
+}
+
+pub fn abs(x: &Number) -> Number {
+    match x {
+        Number::Integer(i) => match i.checked_abs() {
+            Some(res) => Number::Integer(res),
+            None => from_bigint(BigInt::from(*i).abs()),
+        },
+        Number::BigInteger(b) => from_bigint(b.abs()),
+        Number::Float(x) => Number::Float(x.abs()),
+    }
+}
+
+fn is_zero(x: &Number) -> bool {
+    match x {
+        Number::Integer(i) => *i == 0,
+        Number::BigInteger(b) => b.is_zero(),
+        Number::Float(x) => *x == 0.0,
+    }
+}
+
+fn check_divisor(y: &Number) -> Result<(), error::DivisionByZero> {
+    if is_zero(y) {
+        Err(error::DivisionByZero)
+    } else {
+        Ok(())
+    }
+}
+
+// division truncated towards zero, remainder has the sign of dividend
+fn divmod(x: &Number, y: &Number) -> Result<(Number, Number), error::DivisionByZero> {
+    check_divisor(y)?;
+
+    let divmod_big = |x: &BigInt, y: &BigInt| {
+        let (q, r) = x.divmod(y).unwrap();
+        (from_bigint(q), from_bigint(r))
+    };
+
+    let res = match operands(x, y) {
+        Operands::Integers(x, y) => match (x.checked_div(y), x.checked_rem(y)) {
+            (Some(q), Some(r)) => (Number::Integer(q), Number::Integer(r)),
+            _ => divmod_big(&BigInt::from(x), &BigInt::from(y)),
+        },
+        Operands::BigIntegers(x, y) => divmod_big(&x, &y),
+        Operands::Floats(x, y) => (Number::Float((x / y).trunc()), Number::Float(x % y)),
+    };
+
+    Ok(res)
+}
+
+// division of integers stays integer only if it's exact
+pub fn div(x: &Number, y: &Number) -> Result<Number, error::DivisionByZero> {
+    check_divisor(y)?;
+
+    match operands(x, y) {
+        Operands::Floats(x, y) => Ok(Number::Float(x / y)),
+        _ => {
+            let (q, r) = divmod(x, y)?;
+            if is_zero(&r) {
+                Ok(q)
+            } else {
+                Ok(Number::Float(x.to_f64() / y.to_f64()))
+            }
+        }
+    }
+}
+
+pub fn quot(x: &Number, y: &Number) -> Result<Number, error::DivisionByZero> {
+    Ok(divmod(x, y)?.0)
+}
+
+// remainder of `quot`, has the sign of dividend
+pub fn rem(x: &Number, y: &Number) -> Result<Number, error::DivisionByZero> {
+    Ok(divmod(x, y)?.1)
+}
+
+// remainder of floored division, has the sign of divisor
+pub fn modulo(x: &Number, y: &Number) -> Result<Number, error::DivisionByZero> {
+    let r = rem(x, y)?;
+    let is_negative = |n| compare(n, &Number::Integer(0)) == Some(Ordering::Less);
+
+    if !is_zero(&r) && is_negative(&r) != is_negative(y) {
+        Ok(add(&r, y))
+    } else {
+        Ok(r)
+    }
+}
+
+/// Prefix of integer literals in given radix (2 to 36), as read by
+/// lexer. Decimal literals have none.
+pub fn radix_prefix(radix: u32) -> String {
+    match radix {
+        10 => String::new(),
+        16 => "#x".to_string(),
+        8 => "#o".to_string(),
+        2 => "#b".to_string(),
+        r => format!("#{}r", r),
+    }
+}
+
+/// Digits of number in given radix (2 to 36) without prefix. `None` is
+/// returned for floats in radix other than 10.
+pub fn to_string_radix(x: &Number, radix: u32) -> Option<String> {
+    match x {
+        Number::Integer(i) => Some(BigInt::from(*i).to_string_radix(radix)),
+        Number::BigInteger(b) => Some(b.to_string_radix(radix)),
+        Number::Float(x) if radix == 10 => Some(format!("{:?}", x)),
+        Number::Float(_) => None,
+    }
+}
+
+// None is returned when one of the arguments is NaN
+pub fn compare(x: &Number, y: &Number) -> Option<Ordering> {
+    match operands(x, y) {
+        Operands::Integers(x, y) => Some(x.cmp(&y)),
+        Operands::BigIntegers(x, y) => Some(x.cmp(&y)),
+        Operands::Floats(x, y) => x.partial_cmp(&y),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contagion() {
+        assert_eq!(
+            add(&Number::Integer(1), &Number::Integer(2)),
+            Number::Integer(3)
+        );
+        assert_eq!(
+            add(&Number::Integer(1), &Number::Float(0.5)),
+            Number::Float(1.5)
+        );
+        assert_eq!(
+            mul(&Number::Float(2.0), &Number::Integer(3)),
+            Number::Float(6.0)
+        );
+        assert_eq!(
+            sub(&Number::Float(2.5), &Number::Float(0.5)),
+            Number::Float(2.0)
+        );
+    }
+
+    #[test]
+    fn test_division() {
+        let i = Number::Integer;
+        let f = Number::Float;
+
+        assert_eq!(div(&i(6), &i(3)).unwrap(), i(2));
+        assert_eq!(div(&i(7), &i(2)).unwrap(), f(3.5));
+        assert_eq!(div(&f(1.0), &i(4)).unwrap(), f(0.25));
+        assert!(div(&i(1), &i(0)).is_err());
+        assert!(div(&f(1.0), &f(0.0)).is_err());
+
+        assert_eq!(quot(&i(7), &i(2)).unwrap(), i(3));
+        assert_eq!(quot(&i(-7), &i(2)).unwrap(), i(-3));
+        assert_eq!(quot(&f(7.5), &i(2)).unwrap(), f(3.0));
+
+        assert_eq!(rem(&i(7), &i(3)).unwrap(), i(1));
+        assert_eq!(rem(&i(-7), &i(3)).unwrap(), i(-1));
+        assert_eq!(rem(&i(7), &i(-3)).unwrap(), i(1));
+
+        assert_eq!(modulo(&i(7), &i(3)).unwrap(), i(1));
+        assert_eq!(modulo(&i(-7), &i(3)).unwrap(), i(2));
+        assert_eq!(modulo(&i(7), &i(-3)).unwrap(), i(-2));
+        assert_eq!(modulo(&i(-6), &i(3)).unwrap(), i(0));
+        assert_eq!(modulo(&f(-1.5), &i(1)).unwrap(), f(0.5));
+        assert!(modulo(&i(1), &i(0)).is_err());
+    }
+
+    #[test]
+    fn test_to_string_radix() {
+        assert_eq!(
+            to_string_radix(&Number::Integer(-255), 16),
+            Some("-ff".to_string())
+        );
+        assert_eq!(
+            to_string_radix(&Number::Integer(10), 2),
+            Some("1010".to_string())
+        );
+        assert_eq!(
+            to_string_radix(&Number::Float(1.5), 10),
+            Some("1.5".to_string())
+        );
+        assert_eq!(to_string_radix(&Number::Float(1.5), 16), None);
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(
+            compare(&Number::Integer(1), &Number::Float(1.5)),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            compare(&Number::Float(2.0), &Number::Integer(2)),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            compare(&Number::Float(std::f64::NAN), &Number::Integer(2)),
+            None
+        );
+    }
+}
@@ -1,13 +1,20 @@
+use crate::bigint::BigInt;
 use crate::cons::List;
 use crate::env::Env;
+use crate::env::LocalEnv;
 use crate::error;
+use crate::eval::TailCallResult;
+use crate::stream::Stream;
+use im::HashMap;
+use im::HashSet;
+use im::Vector;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
 use std::fmt;
 use std::hash::Hash;
 use std::hash::Hasher;
 use std::rc::Rc;
 
-type EvalResult = Result<LispObject, error::ErrorWithStackTrace>;
-
 macro_rules! define_unwrapper {
     ($id:ident ($enum:ident :: $from:ident) -> $to:ty) => {
         #[allow(unused)]
@@ -33,11 +40,11 @@ macro_rules! define_unwrapper_owned {
 }
 
 #[derive(Clone)]
-pub struct NativeFnWrapper(pub fn(Env, List<LispObject>) -> EvalResult);
+pub struct NativeFnWrapper(pub fn(Env, List<LispObject>) -> TailCallResult);
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[derive(Debug, Clone)]
 pub enum FunctionBody {
-    Interpreted(List<LispObject>),
+    Interpreted(List<LispObject>, LocalEnv),
     Native(NativeFnWrapper),
 }
 
@@ -46,6 +53,9 @@ pub struct FunctionSignature {
     pub name: Option<Symbol>,
     pub arglist: List<Symbol>,
     pub restarg: Option<Symbol>,
+    // number of trailing args in arglist that can be omitted, only
+    // native functions have them
+    pub optional: usize,
 }
 
 impl FunctionSignature {
@@ -54,14 +64,20 @@ impl FunctionSignature {
             name: name,
             arglist: arglist,
             restarg: restarg,
+            optional: 0,
         }
     }
+
+    pub fn required_count(&self) -> usize {
+        self.arglist.len() - self.optional
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[derive(Clone)]
 pub struct Function {
     pub sig: FunctionSignature,
-    pub body: FunctionBody,
+    // shared by clones, so a function object has identity
+    pub body: Rc<FunctionBody>,
 }
 
 impl Function {
@@ -70,10 +86,11 @@ impl Function {
         arglist: List<Symbol>,
         restarg: Option<Symbol>,
         body: List<LispObject>,
+        env: LocalEnv,
     ) -> Self {
         Self {
             sig: FunctionSignature::new(name, arglist, restarg),
-            body: FunctionBody::Interpreted(body),
+            body: Rc::new(FunctionBody::Interpreted(body, env)),
         }
     }
 
@@ -85,9 +102,42 @@ impl Function {
     ) -> Self {
         Self {
             sig: FunctionSignature::new(name, arglist, restarg),
-            body: FunctionBody::Native(body),
+            body: Rc::new(FunctionBody::Native(body)),
         }
     }
+
+    pub fn ptr_eq(&self, rhs: &Self) -> bool {
+        Rc::ptr_eq(&self.body, &rhs.body)
+    }
+
+    fn id(&self) -> usize {
+        Rc::as_ptr(&self.body) as usize
+    }
+}
+
+// closures can capture themselves, so functions are compared, hashed
+// and debug-printed by identity rather than by captured environment
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Function({:?}, 0x{:x})", self.sig, self.id())
+    }
+}
+
+impl PartialEq for Function {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.ptr_eq(rhs)
+    }
+}
+
+impl Eq for Function {}
+
+impl Hash for Function {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        state.write_usize(self.id());
+    }
 }
 
 impl fmt::Debug for NativeFnWrapper {
@@ -117,14 +167,168 @@ impl Copy for NativeFnWrapper {}
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct Symbol(Rc<String>);
 
+thread_local! {
+    // names of all symbols created so far
+    static SYMBOL_NAMES: RefCell<std::collections::HashSet<Rc<String>>> = Default::default();
+}
+
 impl Symbol {
+    /// Symbols are interned, i.e. symbols with the same name share it,
+    /// so they can be compared by identity.
     pub fn new(s: impl Into<String>) -> Self {
-        Symbol(Rc::new(s.into()))
+        let name = s.into();
+
+        SYMBOL_NAMES.with(|names| {
+            let mut names = names.borrow_mut();
+            match names.get(&name) {
+                Some(name) => Symbol(name.clone()),
+                None => {
+                    let name = Rc::new(name);
+                    names.insert(name.clone());
+                    Symbol(name)
+                }
+            }
+        })
+    }
+
+    pub fn ptr_eq(&self, rhs: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &rhs.0)
     }
 
     pub fn name(&self) -> String {
         self.0.as_ref().clone()
     }
+
+    /// Keywords are symbols starting with `:`, they evaluate to
+    /// themselves and can't be bound.
+    pub fn is_keyword(&self) -> bool {
+        self.0.starts_with(':')
+    }
+}
+
+// f64 is neither Eq nor Hash, so floats are compared and hashed bitwise
+#[derive(Debug, Clone, Copy)]
+pub struct Float(pub f64);
+
+impl Hash for Float {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        state.write_u64(self.0.to_bits());
+    }
+}
+
+impl PartialEq for Float {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.0.to_bits() == rhs.0.to_bits()
+    }
+}
+
+impl Eq for Float {}
+
+// iteration order of im collections may differ for equal ones, so
+// hashes of elements are combined in an order-independent way
+fn hash_unordered<T: Hash, H: Hasher>(len: usize, items: impl Iterator<Item = T>, state: &mut H) {
+    let items_hash = items.fold(0u64, |acc, item| {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        acc.wrapping_add(hasher.finish())
+    });
+
+    state.write_usize(len);
+    state.write_u64(items_hash);
+}
+
+/// Persistent hash map with arbitrary Lisp keys, including maps themselves.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Map(pub HashMap<LispObject, LispObject>);
+
+impl Map {
+    /// Entries ordered by printed keys, unlike iteration order of
+    /// hash map it's the same between runs.
+    pub fn sorted_entries(&self) -> Vec<(&LispObject, &LispObject)> {
+        let mut entries: Vec<_> = self.0.iter().map(|(k, v)| (k.to_string(), k, v)).collect();
+        entries.sort_by(|x, y| x.0.cmp(&y.0));
+        entries.into_iter().map(|(_, k, v)| (k, v)).collect()
+    }
+}
+
+impl Hash for Map {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        hash_unordered(self.0.len(), self.0.iter(), state);
+    }
+}
+
+/// Persistent hash set of arbitrary Lisp objects.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Set(pub HashSet<LispObject>);
+
+impl Set {
+    /// Items ordered by their printed form, see `Map::sorted_entries`.
+    pub fn sorted_items(&self) -> Vec<&LispObject> {
+        let mut items: Vec<_> = self.0.iter().map(|x| (x.to_string(), x)).collect();
+        items.sort_by(|x, y| x.0.cmp(&y.0));
+        items.into_iter().map(|(_, x)| x).collect()
+    }
+}
+
+impl Hash for Set {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        hash_unordered(self.0.len(), self.0.iter(), state);
+    }
+}
+
+/// Mutable reference cell, clones refer to the same cell and equality
+/// is identity.
+#[derive(Clone)]
+pub struct MutableBox(Rc<RefCell<LispObject>>);
+
+impl MutableBox {
+    pub fn new(value: LispObject) -> Self {
+        MutableBox(Rc::new(RefCell::new(value)))
+    }
+
+    pub fn get(&self) -> LispObject {
+        self.0.borrow().clone()
+    }
+
+    pub fn set(&self, value: LispObject) {
+        *self.0.borrow_mut() = value;
+    }
+
+    pub(crate) fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+}
+
+impl fmt::Debug for MutableBox {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MutableBox(0x{:x})", self.id())
+    }
+}
+
+impl PartialEq for MutableBox {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.id() == rhs.id()
+    }
+}
+
+impl Eq for MutableBox {}
+
+impl Hash for MutableBox {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        state.write_usize(self.id());
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
@@ -132,25 +336,80 @@ pub enum LispObject {
     T,
     Symbol(Symbol),
     Integer(i64),
+    BigInteger(BigInt),
+    Float(Float),
     String(String),
+    Char(char),
     List(List<LispObject>),
+    Vector(Vector<LispObject>),
+    Map(Map),
+    Set(Set),
+    Box(MutableBox),
     Fn(Function),
+    Stream(Stream),
 }
 
 impl LispObject {
     pub fn nil() -> Self {
         LispObject::List(List::empty())
     }
+
+    /// Identity comparison: symbols with the same name, the same
+    /// non-empty list, vector, map, set, function, box or stream. Empty
+    /// collections are identical to each other. Numbers, chars and
+    /// strings are stored by value without identity, so they're
+    /// compared by type and value instead, floats bitwise.
+    pub fn is_eq(&self, rhs: &Self) -> bool {
+        match (self, rhs) {
+            (LispObject::T, LispObject::T) => true,
+            (LispObject::Integer(x), LispObject::Integer(y)) => x == y,
+            (LispObject::BigInteger(x), LispObject::BigInteger(y)) => x == y,
+            (LispObject::Float(x), LispObject::Float(y)) => x == y,
+            (LispObject::Char(x), LispObject::Char(y)) => x == y,
+            (LispObject::String(x), LispObject::String(y)) => x == y,
+            (LispObject::Symbol(x), LispObject::Symbol(y)) => x.ptr_eq(y),
+            (LispObject::List(x), LispObject::List(y)) => x.cell_id() == y.cell_id(),
+            (LispObject::Vector(x), LispObject::Vector(y)) => {
+                x.ptr_eq(y) || (x.is_empty() && y.is_empty())
+            }
+            (LispObject::Map(x), LispObject::Map(y)) => {
+                x.0.ptr_eq(&y.0) || (x.0.is_empty() && y.0.is_empty())
+            }
+            (LispObject::Set(x), LispObject::Set(y)) => {
+                x.0.ptr_eq(&y.0) || (x.0.is_empty() && y.0.is_empty())
+            }
+            (LispObject::Fn(x), LispObject::Fn(y)) => x.ptr_eq(y),
+            (LispObject::Box(x), LispObject::Box(y)) => x == y,
+            (LispObject::Stream(x), LispObject::Stream(y)) => x == y,
+            _ => false,
+        }
+    }
 }
 
 define_unwrapper!(to_symbol(LispObject :: Symbol) -> Symbol);
 define_unwrapper!(to_i64(LispObject :: Integer) -> i64);
+define_unwrapper!(to_bigint(LispObject :: BigInteger) -> BigInt);
+define_unwrapper!(to_float(LispObject :: Float) -> Float);
 define_unwrapper!(to_string(LispObject :: String) -> String);
+define_unwrapper!(to_char(LispObject :: Char) -> char);
 define_unwrapper!(to_list(LispObject :: List) -> List<LispObject>);
+define_unwrapper!(to_vector(LispObject :: Vector) -> Vector<LispObject>);
+define_unwrapper!(to_map(LispObject :: Map) -> Map);
+define_unwrapper!(to_set(LispObject :: Set) -> Set);
+define_unwrapper!(to_box(LispObject :: Box) -> MutableBox);
 define_unwrapper!(to_function(LispObject :: Fn) -> Function);
+define_unwrapper!(to_stream(LispObject :: Stream) -> Stream);
 
 define_unwrapper_owned!(to_symbol_owned(LispObject :: Symbol) -> Symbol);
 define_unwrapper_owned!(to_i64_owned(LispObject :: Integer) -> i64);
+define_unwrapper_owned!(to_bigint_owned(LispObject :: BigInteger) -> BigInt);
+define_unwrapper_owned!(to_float_owned(LispObject :: Float) -> Float);
 define_unwrapper_owned!(to_string_owned(LispObject :: String) -> String);
+define_unwrapper_owned!(to_char_owned(LispObject :: Char) -> char);
 define_unwrapper_owned!(to_list_owned(LispObject :: List) -> List<LispObject>);
+define_unwrapper_owned!(to_vector_owned(LispObject :: Vector) -> Vector<LispObject>);
+define_unwrapper_owned!(to_map_owned(LispObject :: Map) -> Map);
+define_unwrapper_owned!(to_set_owned(LispObject :: Set) -> Set);
+define_unwrapper_owned!(to_box_owned(LispObject :: Box) -> MutableBox);
 define_unwrapper_owned!(to_function_owned(LispObject :: Fn) -> Function);
+define_unwrapper_owned!(to_stream_owned(LispObject :: Stream) -> Stream);
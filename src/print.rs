@@ -1,14 +1,27 @@
+use crate::cons::List;
 use crate::env::StackFrameDesignator;
 use crate::env::StackTrace;
+use crate::lexer::CHAR_NAMES;
+use crate::lexer::SPECIAL_FLOATS;
 use crate::object;
 use crate::object::LispObject;
+use crate::object::Map;
+use crate::object::MutableBox;
+use crate::object::Set;
+use im::Vector;
+use std::cell::RefCell;
 use std::fmt;
 
+thread_local! {
+    // ids of boxes whose contents are being printed, to cut cycles
+    static PRINTED_BOXES: RefCell<Vec<usize>> = Default::default();
+}
+
 impl fmt::Display for object::Function {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        match self.body {
+        match *self.body {
             object::FunctionBody::Native(_) => write!(f, "#<NATIVE-FN>"),
-            object::FunctionBody::Interpreted(_) => write!(f, "#<INTERPRETED-FN>"),
+            object::FunctionBody::Interpreted(..) => write!(f, "#<INTERPRETED-FN>"),
         }
     }
 }
@@ -19,16 +32,126 @@ impl fmt::Display for object::Symbol {
     }
 }
 
+fn quote_char(list: &List<LispObject>) -> Option<&'static str> {
+    if list.len() != 2 {
+        return None;
+    }
+
+    match list.ufirst() {
+        LispObject::Symbol(s) => match s.name().as_str() {
+            "quote" => Some("'"),
+            "qquote" => Some("`"),
+            "unq" => Some(","),
+            "unqs" => Some(",@"),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn write_escaped_string(f: &mut fmt::Formatter, s: &str) -> Result<(), fmt::Error> {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            '\r' => write!(f, "\\r")?,
+            c if c.is_control() => write!(f, "\\u{{{:x}}}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+fn write_char(f: &mut fmt::Formatter, c: char) -> Result<(), fmt::Error> {
+    match CHAR_NAMES.iter().find(|(_, named)| *named == c) {
+        Some((name, _)) => write!(f, "#\\{}", name),
+        None if c.is_control() => write!(f, "#\\x{:x}", c as u32),
+        None => write!(f, "#\\{}", c),
+    }
+}
+
+fn write_float(f: &mut fmt::Formatter, x: f64) -> Result<(), fmt::Error> {
+    // NaNs with any sign and payload are printed the same way
+    let special = SPECIAL_FLOATS
+        .iter()
+        .find(|(_, special)| x == *special || (x.is_nan() && special.is_nan()));
+
+    match special {
+        Some((name, _)) => write!(f, "{}", name),
+        None => write!(f, "{:?}", x),
+    }
+}
+
+fn write_vector(f: &mut fmt::Formatter, v: &Vector<LispObject>) -> Result<(), fmt::Error> {
+    write!(f, "[")?;
+    for (i, x) in v.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{}", x)?;
+    }
+    write!(f, "]")
+}
+
+fn write_map(f: &mut fmt::Formatter, m: &Map) -> Result<(), fmt::Error> {
+    write!(f, "{{")?;
+    for (i, (k, v)) in m.sorted_entries().into_iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{} {}", k, v)?;
+    }
+    write!(f, "}}")
+}
+
+fn write_set(f: &mut fmt::Formatter, s: &Set) -> Result<(), fmt::Error> {
+    write!(f, "#{{")?;
+    for (i, x) in s.sorted_items().into_iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{}", x)?;
+    }
+    write!(f, "}}")
+}
+
+fn write_box(f: &mut fmt::Formatter, b: &MutableBox) -> Result<(), fmt::Error> {
+    let id = b.id();
+    if PRINTED_BOXES.with(|boxes| boxes.borrow().contains(&id)) {
+        return write!(f, "#<box ...>");
+    }
+
+    PRINTED_BOXES.with(|boxes| boxes.borrow_mut().push(id));
+    let res = write!(f, "#<box {}>", b.get());
+    PRINTED_BOXES.with(|boxes| boxes.borrow_mut().pop());
+    res
+}
+
 impl fmt::Display for object::LispObject {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match self {
             LispObject::List(list) if list.is_empty() => write!(f, "nil"),
             LispObject::T => write!(f, "t"),
             LispObject::Integer(i) => write!(f, "{}", i),
-            LispObject::String(s) => write!(f, "\"{}\"", s),
+            LispObject::BigInteger(b) => write!(f, "{}", b),
+            // Debug output always has a fraction or an exponent, so it's read back as float
+            LispObject::Float(x) => write_float(f, x.0),
+            LispObject::String(s) => write_escaped_string(f, s),
+            LispObject::Char(c) => write_char(f, *c),
             LispObject::Fn(func) => write!(f, "{}", func),
+            LispObject::Vector(v) => write_vector(f, v),
+            LispObject::Map(m) => write_map(f, m),
+            LispObject::Set(s) => write_set(f, s),
+            LispObject::Box(b) => write_box(f, b),
+            LispObject::Stream(_) => write!(f, "#<STREAM>"),
             LispObject::Symbol(s) => write!(f, "{}", s),
-            LispObject::List(list) => write!(f, "{}", list),
+            LispObject::List(list) => match quote_char(list) {
+                Some(c) => write!(f, "{}{}", c, list.tail().ufirst()),
+                None => write!(f, "{}", list),
+            },
         }
     }
 }
@@ -49,11 +172,141 @@ impl fmt::Display for object::FunctionSignature {
 
 pub fn print_stack_trace(trace: &StackTrace) {
     println!("stack trace:");
-    for designator in trace.iter() {
-        match designator {
-            StackFrameDesignator::Top => println!("  <top>"),
-            StackFrameDesignator::Name(sym) => println!("  {}", sym),
-            StackFrameDesignator::Signature(sig) => println!("  {}", sig),
+    for frame in trace.iter() {
+        let name = match &frame.designator {
+            StackFrameDesignator::Top => "<top>".to_string(),
+            StackFrameDesignator::Name(sym) => format!("{}", sym),
+            StackFrameDesignator::Signature(sig) => format!("{}", sig),
+        };
+
+        match &frame.location {
+            Some(location) => println!("  {} at {}", name, location),
+            None => println!("  {}", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::object::Float;
+    use crate::object::LispObject;
+    use crate::object::MutableBox;
+    use crate::test_utils::read;
+
+    #[test]
+    fn test_quote_chars() {
+        assert_eq!(format!("{}", read("(quote x)")), "'x");
+        assert_eq!(
+            format!("{}", read("(qquote (a (unq b) (unqs c)))")),
+            "`(a ,b ,@c)"
+        );
+        assert_eq!(format!("{}", read("''x")), "''x");
+        assert_eq!(format!("{}", read("(quote x y)")), "(quote x y)");
+        assert_eq!(format!("{}", read("(quote)")), "(quote)");
+    }
+
+    #[test]
+    fn test_float() {
+        for s in &["1.5", "1.0", "0.1", "1e20", "1e-7", "123456.789"] {
+            let printed = format!("{}", read(*s));
+            assert_eq!(read(printed.as_str()), read(*s));
+        }
+
+        assert_eq!(format!("{}", read("1.0")), "1.0");
+        assert_eq!(format!("{}", read("2e3")), "2000.0");
+
+        for (x, printed) in &[
+            (f64::INFINITY, "+inf.0"),
+            (f64::NEG_INFINITY, "-inf.0"),
+            (f64::NAN, "+nan.0"),
+            (-f64::NAN, "+nan.0"),
+        ] {
+            let x = LispObject::Float(Float(*x));
+            assert_eq!(format!("{}", x), *printed);
+            assert_eq!(format!("{}", read(*printed)), *printed);
+        }
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let s = LispObject::String("say \"hi\"\n\\\t\r\u{7}".to_string());
+        let printed = format!("{}", s);
+
+        assert_eq!(printed, r#""say \"hi\"\n\\\t\r\u{7}""#);
+        assert_eq!(read(printed), s);
+
+        assert_eq!(format!("{}", read("\"привет 🦀\"")), "\"привет 🦀\"");
+    }
+
+    #[test]
+    fn test_chars() {
+        for (c, printed) in &[
+            ('a', r"#\a"),
+            ('λ', r"#\λ"),
+            ('(', r"#\("),
+            (' ', r"#\space"),
+            ('\n', r"#\newline"),
+            ('\u{7}', r"#\x7"),
+        ] {
+            let c = LispObject::Char(*c);
+            assert_eq!(format!("{}", c), *printed);
+            assert_eq!(read(*printed), c);
         }
     }
+
+    #[test]
+    fn test_vectors() {
+        for s in &["[]", "[1 \"a\" #\\b]", "[[x] (y z) []]"] {
+            let printed = format!("{}", read(*s));
+            assert_eq!(printed, *s);
+            assert_eq!(read(printed), read(*s));
+        }
+
+        assert_eq!(format!("{}", read("#(1 2)")), "[1 2]");
+    }
+
+    #[test]
+    fn test_maps() {
+        for s in &["{}", "{:a [1 2]}", "{{} {(x) \"y\"}}", "{:a 1 :b 2 :c 3}"] {
+            let printed = format!("{}", read(*s));
+            assert_eq!(read(printed), read(*s));
+        }
+
+        assert_eq!(format!("{}", read("{:a (b)}")), "{:a (b)}");
+        assert_eq!(
+            format!("{}", read("{:c 3 :a 1 \"b\" 2 (d) 4}")),
+            "{\"b\" 2 (d) 4 :a 1 :c 3}"
+        );
+    }
+
+    #[test]
+    fn test_sets() {
+        for s in &["#{}", "#{1 2 3}", "#{#{} {:a #{b}} [c]}"] {
+            let printed = format!("{}", read(*s));
+            assert_eq!(read(printed), read(*s));
+        }
+
+        assert_eq!(format!("{}", read("#{(a)}")), "#{(a)}");
+        assert_eq!(format!("{}", read("#{c 3 a (b) 1}")), "#{(b) 1 3 a c}");
+    }
+
+    #[test]
+    fn test_boxes() {
+        let b = LispObject::Box(MutableBox::new(read("(1 [2])")));
+        assert_eq!(format!("{}", b), "#<box (1 [2])>");
+
+        let inner = MutableBox::new(read("1"));
+        let outer = MutableBox::new(LispObject::Box(inner.clone()));
+        inner.set(read("(a)"));
+        assert_eq!(
+            format!("{}", LispObject::Box(outer.clone())),
+            "#<box #<box (a)>>"
+        );
+
+        inner.set(LispObject::Box(outer.clone()));
+        assert_eq!(
+            format!("{}", LispObject::Box(outer)),
+            "#<box #<box #<box ...>>>"
+        );
+    }
 }
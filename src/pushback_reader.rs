@@ -1,12 +1,12 @@
 use std::io::Read;
 use std::io::Result;
 
-pub struct PushbackReader<'a, T: Read + 'a> {
-    reader: &'a mut T,
+pub struct PushbackReader<T: Read> {
+    reader: T,
     buffer: Vec<u8>,
 }
 
-impl<'a, T: Read + 'a> Read for PushbackReader<'a, T> {
+impl<T: Read> Read for PushbackReader<T> {
     fn read(&mut self, dest: &mut [u8]) -> Result<usize> {
         let dest_size = dest.len();
         let buf_size = self.buffer.len();
@@ -25,8 +25,8 @@ impl<'a, T: Read + 'a> Read for PushbackReader<'a, T> {
     }
 }
 
-impl<'a, T: Read + 'a> PushbackReader<'a, T> {
-    pub fn create(r: &'a mut T) -> PushbackReader<'a, T> {
+impl<T: Read> PushbackReader<T> {
+    pub fn create(r: T) -> PushbackReader<T> {
         PushbackReader {
             reader: r,
             buffer: Vec::new(),
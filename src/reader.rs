@@ -1,27 +1,100 @@
 use crate::cons::List;
+use crate::env::Env;
+use crate::error::EndOfInput;
 use crate::error::SyntaxError;
+use crate::eval;
 use crate::lexer::Lexer;
 use crate::lexer::Token;
+use crate::location::SourceLocation;
+use crate::location::SourceMap;
+use crate::object::Float;
 use crate::object::LispObject;
+use crate::object::Map;
+use crate::object::Set;
 use crate::object::Symbol;
+use crate::stream::Stream;
+use im::HashMap;
+use im::HashSet;
+use im::Vector;
 use std::error::Error;
 use std::io::Read;
 use std::io;
 
-pub struct Reader<'a, T: Read + 'a> {
-    lexer: Lexer<'a, T>,
+pub struct Reader {
+    input: Stream,
+    source_map: Option<SourceMap>,
+    // location of the last form read, recorded along with source map
+    form_location: Option<SourceLocation>,
+    // environment in which reader macros are looked up and called
+    env: Option<Env>,
 }
 
-impl<'a, T: Read + 'a> Reader<'a, T> {
-    pub fn create(r: &'a mut T) -> Reader<'a, T> {
+impl Reader {
+    pub fn create(r: impl Read + 'static) -> Reader {
         Reader {
-            lexer: Lexer::create(r),
+            input: Stream::new(Lexer::create(r)),
+            source_map: None,
+            form_location: None,
+            env: None,
         }
     }
 
-    fn next_tok_or_eof(&mut self) -> Result<Token, Box<Error>> {
-        let tok = self.lexer.next_token()?;
-        tok.ok_or(Box::new(io::Error::from(io::ErrorKind::UnexpectedEof)))
+    /// Creates reader which records locations of read lists in source
+    /// map of `env` and expands reader macros defined in `env`.
+    pub fn create_with_env(r: impl Read + 'static, file: impl Into<String>, env: Env) -> Reader {
+        Reader {
+            input: Stream::new(Lexer::create_with_file(r, file)),
+            source_map: Some(env.source_map().clone()),
+            form_location: None,
+            env: Some(env),
+        }
+    }
+
+    /// Creates reader of data from `stream`, reader macros defined in
+    /// `env` are expanded. Locations of read lists are not recorded.
+    pub fn create_from_stream(stream: Stream, env: Env) -> Reader {
+        Reader {
+            input: stream,
+            source_map: None,
+            form_location: None,
+            env: Some(env),
+        }
+    }
+
+    // next token after skipping datums commented out with `#;`
+    fn next_token(&mut self) -> Result<Option<Token>, Box<dyn Error>> {
+        loop {
+            let tok = self.input.input().next_token()?;
+            match tok {
+                Some(Token::DatumComment) => {
+                    let location = self.input.input().token_location();
+                    // locations of skipped forms are not recorded
+                    let source_map = self.source_map.take();
+                    let skipped = self
+                        .next_tok_or_eof()
+                        .and_then(|tok| self.read_form_from_tok(tok));
+                    self.source_map = source_map;
+                    skipped.map_err(|e| Self::locate_end_of_input(e, &location))?;
+                }
+                tok => return Ok(tok),
+            }
+        }
+    }
+
+    // end of input is reported as `EndOfInput` without location, which
+    // is given to it by the unclosed form being read
+    fn next_tok_or_eof(&mut self) -> Result<Token, Box<dyn Error>> {
+        let tok = self.next_token()?;
+        tok.ok_or_else(|| Box::new(EndOfInput::new(true)) as Box<dyn Error>)
+    }
+
+    // gives end of input error location of the form it ended in, unless
+    // it already has one of an inner form
+    fn locate_end_of_input(e: Box<dyn Error>, location: &SourceLocation) -> Box<dyn Error> {
+        match e.downcast::<EndOfInput>() {
+            Ok(e) => Box::new(e.with_location(location.clone())),
+            Err(e) => e,
+        }
     }
 
     fn tok_to_trivial_form(&self, tok: &Token) -> Option<LispObject> {
@@ -30,56 +103,204 @@ impl<'a, T: Read + 'a> Reader<'a, T> {
             Token::Symbol(s) if s == "t" => Some(LispObject::T),
             Token::Symbol(s) => Some(LispObject::Symbol(Symbol::new(s.clone()))),
             Token::IntegerLiteral(i) => Some(LispObject::Integer(*i)),
+            Token::BigIntegerLiteral(b) => Some(LispObject::BigInteger(b.clone())),
+            Token::FloatLiteral(x) => Some(LispObject::Float(Float(*x))),
             Token::StringLiteral(s) => Some(LispObject::String(s.to_string())),
+            Token::CharLiteral(c) => Some(LispObject::Char(*c)),
             _ => None,
         }
     }
 
-    fn read_list_form(&mut self) -> Result<LispObject, Box<Error>> {
-        let mut vec = Vec::new();
+    fn quote_char_symbol(tok: &Token) -> Option<&'static str> {
+        match tok {
+            Token::Quote => Some("quote"),
+            Token::Quasiquote => Some("qquote"),
+            Token::Unquote => Some("unq"),
+            Token::UnquoteSplicing => Some("unqs"),
+            _ => None,
+        }
+    }
 
-        let mut tok = self.next_tok_or_eof()?;
+    fn read_quoted_form(&mut self, quote_sym: &str) -> Result<LispObject, Box<dyn Error>> {
+        let tok = self.next_tok_or_eof()?;
+        let form = self.read_form_from_tok(tok)?;
+
+        Ok(LispObject::List(
+            List::empty()
+                .cons(form)
+                .cons(LispObject::Symbol(Symbol::new(quote_sym))),
+        ))
+    }
+
+    fn read_form_from_tok(&mut self, tok: Token) -> Result<LispObject, Box<dyn Error>> {
+        if let Some(form) = self.tok_to_trivial_form(&tok) {
+            return Ok(form);
+        }
 
-        while tok != Token::RightPar {
-            let form;
+        let location = self.input.input().token_location();
 
-            if let Some(t_form) = self.tok_to_trivial_form(&tok) {
-                form = t_form;
-            } else {
-                form = match tok {
-                    Token::LeftPar => self.read_list_form()?,
-                    Token::RightPar => break,
-                    tok => panic!("unexpected token {:?}", tok),
+        self.read_compound_form(tok, location.clone())
+            .map_err(|e| Self::locate_end_of_input(e, &location))
+    }
+
+    // reads form starting with `tok` at `location`, which is not a
+    // single token
+    fn read_compound_form(
+        &mut self,
+        tok: Token,
+        location: SourceLocation,
+    ) -> Result<LispObject, Box<dyn Error>> {
+        let form = if let Some(quote_sym) = Self::quote_char_symbol(&tok) {
+            self.read_quoted_form(quote_sym)?
+        } else {
+            match tok {
+                Token::LeftPar => LispObject::List(self.read_list()?),
+                Token::LeftBracket => {
+                    LispObject::Vector(Vector::from(self.read_forms_until(Token::RightBracket)?))
+                }
+                Token::HashLeftPar => {
+                    LispObject::Vector(Vector::from(self.read_forms_until(Token::RightPar)?))
                 }
+                Token::LeftBrace => self.read_map(&location)?,
+                Token::HashLeftBrace => LispObject::Set(Set(HashSet::from(
+                    self.read_forms_until(Token::RightBrace)?,
+                ))),
+                Token::DispatchMacro(c) => self.call_dispatch_macro(c, &location)?,
+                Token::RightPar => {
+                    Err(SyntaxError::new("unbalanced parens").with_location(location.clone()))?
+                }
+                Token::RightBracket => {
+                    Err(SyntaxError::new("unbalanced brackets").with_location(location.clone()))?
+                }
+                Token::RightBrace => {
+                    Err(SyntaxError::new("unbalanced braces").with_location(location.clone()))?
+                }
+                tok => Err(
+                    SyntaxError::new(format!("unexpected token {:?}", tok))
+                        .with_location(location.clone()),
+                )?,
             }
+        };
+
+        if let (Some(source_map), LispObject::List(list)) = (&self.source_map, &form) {
+            source_map.insert(list, location);
+        }
 
-            vec.push(form);
+        Ok(form)
+    }
+
+    // calls function defined for `#c` with input stream and `c`, form
+    // returned by it is the read one
+    fn call_dispatch_macro(
+        &mut self,
+        c: char,
+        location: &SourceLocation,
+    ) -> Result<LispObject, Box<dyn Error>> {
+        let (env, f) = self
+            .env
+            .as_ref()
+            .and_then(|env| Some((env.clone(), env.lookup_dispatch_macro(c)?)))
+            .ok_or_else(|| {
+                SyntaxError::new(format!("undefined dispatch macro #{}", c))
+                    .with_location(location.clone())
+            })?;
+
+        let args = List::empty()
+            .cons(LispObject::Char(c))
+            .cons(LispObject::Stream(self.input.clone()));
+
+        Ok(eval::call_function_object(env, &f, args, false, None)?)
+    }
+
+    // reads forms up to closing `end` token, along with locations of
+    // their first tokens
+    fn read_located_forms_until(
+        &mut self,
+        end: Token,
+    ) -> Result<Vec<(LispObject, SourceLocation)>, Box<dyn Error>> {
+        let mut vec = Vec::new();
+
+        let mut tok = self.next_tok_or_eof()?;
+
+        while tok != end {
+            let location = self.input.input().token_location();
+            vec.push((self.read_form_from_tok(tok)?, location));
             tok = self.next_tok_or_eof()?;
         }
 
-        Ok(LispObject::List(List::from_rev_iter(vec)))
+        Ok(vec)
     }
 
-    pub fn read_form(&mut self) -> Result<Option<LispObject>, Box<Error>> {
-        let tok = self.lexer.next_token()?;
+    // reads forms up to closing `end` token
+    fn read_forms_until(&mut self, end: Token) -> Result<Vec<LispObject>, Box<dyn Error>> {
+        let forms = self.read_located_forms_until(end)?;
+        Ok(forms.into_iter().map(|(form, _)| form).collect())
+    }
 
-        if tok.is_none() {
-            return Ok(None);
+    // reads list elements up to closing `)`, locations of symbols among
+    // them are recorded in source map
+    fn read_list(&mut self) -> Result<List<LispObject>, Box<dyn Error>> {
+        let forms = self.read_located_forms_until(Token::RightPar)?;
+        let list = List::from_iter(forms.iter().map(|(form, _)| form.clone()));
+
+        if let Some(source_map) = &self.source_map {
+            let mut cell = list.clone();
+            for (form, location) in forms {
+                if let LispObject::Symbol(_) = form {
+                    source_map.insert_symbol(&cell, location);
+                }
+                cell = cell.tail();
+            }
         }
 
-        let tok = tok.unwrap();
+        Ok(list)
+    }
 
-        let trivial_form = self.tok_to_trivial_form(&tok);
-        let form = match trivial_form {
-            Some(form) => form,
-            None => match tok {
-                Token::LeftPar => self.read_list_form()?,
-                Token::RightPar => Err(SyntaxError::new("unbalanced parens"))?,
-                tok => panic!("unexpected token {:?}", tok),
-            },
-        };
+    // reads keys and values of map literal up to closing `}`
+    fn read_map(&mut self, location: &SourceLocation) -> Result<LispObject, Box<dyn Error>> {
+        let forms = self.read_forms_until(Token::RightBrace)?;
+
+        if forms.len() % 2 != 0 {
+            Err(
+                SyntaxError::new("map literal must have an even number of forms")
+                    .with_location(location.clone()),
+            )?
+        }
+
+        let mut map = HashMap::new();
+        let mut forms = forms.into_iter();
+
+        while let (Some(k), Some(v)) = (forms.next(), forms.next()) {
+            map.insert(k, v);
+        }
+
+        Ok(LispObject::Map(Map(map)))
+    }
 
-        Ok(Some(form))
+    /// Drops the rest of malformed form's line, so reading can be
+    /// resumed from the next line.
+    pub fn skip_rest_of_line(&mut self) -> io::Result<()> {
+        self.input.input().skip_rest_of_line()
+    }
+
+    /// Location where the last read form starts, it's known only for
+    /// readers which record locations in source map.
+    pub fn form_location(&self) -> Option<SourceLocation> {
+        self.form_location.clone()
+    }
+
+    pub fn read_form(&mut self) -> Result<Option<LispObject>, Box<dyn Error>> {
+        let tok = self.next_token()?;
+
+        match tok {
+            Some(tok) => {
+                if self.source_map.is_some() {
+                    self.form_location = Some(self.input.input().token_location());
+                }
+                Ok(Some(self.read_form_from_tok(tok)?))
+            }
+            None => Ok(None),
+        }
     }
 }
 
@@ -87,17 +308,20 @@ impl<'a, T: Read + 'a> Reader<'a, T> {
 mod tests {
     use super::*;
     use crate::test_utils::is_gen_eof;
+    use crate::test_utils::read;
+    use crate::bigint::BigInt;
     use crate::cons::List;
+    use crate::object::to_list;
 
     #[test]
     fn test_empty() {
-        let mut input = "".as_bytes();
-        let mut reader = Reader::create(&mut input);
+        let input = "".as_bytes();
+        let mut reader = Reader::create(input);
 
         assert_eq!(reader.read_form().unwrap(), None);
 
-        let mut input = "foo".as_bytes();
-        let mut reader = Reader::create(&mut input);
+        let input = "foo".as_bytes();
+        let mut reader = Reader::create(input);
 
         let _ = reader.read_form().unwrap().unwrap();
         assert_eq!(reader.read_form().unwrap(), None);
@@ -105,8 +329,8 @@ mod tests {
 
     #[test]
     fn test_integer_literal() {
-        let mut input = "1 12 1000 2019".as_bytes();
-        let mut reader = Reader::create(&mut input);
+        let input = "1 12 1000 2019".as_bytes();
+        let mut reader = Reader::create(input);
 
         assert_eq!(reader.read_form().unwrap().unwrap(), LispObject::Integer(1));
         assert_eq!(
@@ -123,10 +347,97 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_float_literal() {
+        let input = "1.5 1e3".as_bytes();
+        let mut reader = Reader::create(input);
+
+        assert_eq!(
+            reader.read_form().unwrap().unwrap(),
+            LispObject::Float(Float(1.5))
+        );
+        assert_eq!(
+            reader.read_form().unwrap().unwrap(),
+            LispObject::Float(Float(1000.0))
+        );
+    }
+
+    #[test]
+    fn test_locations() {
+        let env = Env::new();
+        let source_map = env.source_map();
+        let input = "(a\n  (b c) 'd)\n)".as_bytes();
+        let mut reader = Reader::create_with_env(input, "foo.unl", env.clone());
+
+        let form = reader.read_form().unwrap().unwrap();
+        let list = to_list(&form).unwrap();
+        let location_of = |form: &LispObject| {
+            let list = to_list(form).unwrap();
+            source_map.get(list).map(|l| format!("{}", l))
+        };
+
+        assert_eq!(location_of(&form), Some("foo.unl:1:1".to_string()));
+        assert_eq!(
+            location_of(list.iter().nth(1).unwrap()),
+            Some("foo.unl:2:3".to_string())
+        );
+        assert_eq!(
+            location_of(list.iter().nth(2).unwrap()),
+            Some("foo.unl:2:9".to_string())
+        );
+
+        let err = reader.read_form().unwrap_err();
+        assert_eq!(format!("{}", err), "foo.unl:3:1: unbalanced parens");
+    }
+
+    #[test]
+    fn test_skipped_forms_locations() {
+        let env = Env::new();
+        let input = "#;(a (b)) (c)".as_bytes();
+        let mut reader = Reader::create_with_env(input, "foo.unl", env.clone());
+
+        let form = reader.read_form().unwrap().unwrap();
+        assert_eq!(form, read("(c)"));
+        assert_eq!(env.source_map().entries_count(), 1);
+    }
+
+    #[test]
+    fn test_recovery() {
+        let input: &[u8] = b"(a \"\\q\" b)\n(c) \xff (d)\n) (e)\n(f)";
+        let mut reader = Reader::create(input);
+
+        let mut forms = vec![];
+        loop {
+            match reader.read_form() {
+                Ok(Some(form)) => forms.push(form),
+                Ok(None) => break,
+                Err(e) => {
+                    assert!(e.downcast_ref::<SyntaxError>().is_some());
+                    reader.skip_rest_of_line().unwrap();
+                }
+            }
+        }
+
+        assert_eq!(forms, vec![read("(c)"), read("(f)")]);
+    }
+
+    #[test]
+    fn test_bigint_literal() {
+        let input = "123456789012345678901234567890".as_bytes();
+        let mut reader = Reader::create(input);
+
+        assert_eq!(
+            reader.read_form().unwrap().unwrap(),
+            LispObject::BigInteger(
+                BigInt::parse_radix("123456789012345678901234567890", 10).unwrap()
+            )
+        );
+    }
+
     #[test]
     fn test_string_literal() {
-        let mut input = "\"\" \"foo\" \"bar\"".as_bytes();
-        let mut reader = Reader::create(&mut input);
+        let input = "\"\" \"foo\" \"bar\"".as_bytes();
+        let mut reader = Reader::create(input);
 
         assert_eq!(
             reader.read_form().unwrap().unwrap(),
@@ -144,8 +455,8 @@ mod tests {
 
     #[test]
     fn test_symbol() {
-        let mut input = "x foo bar*".as_bytes();
-        let mut reader = Reader::create(&mut input);
+        let input = "x foo bar*".as_bytes();
+        let mut reader = Reader::create(input);
 
         assert_eq!(
             reader.read_form().unwrap().unwrap(),
@@ -163,8 +474,8 @@ mod tests {
 
     #[test]
     fn test_list() {
-        let mut input = "() (foo bar) (foo (bar baz) quux)".as_bytes();
-        let mut reader = Reader::create(&mut input);
+        let input = "() (foo bar) (foo (bar baz) quux)".as_bytes();
+        let mut reader = Reader::create(input);
 
         let sym = |x| LispObject::Symbol(Symbol::new(x));
 
@@ -186,8 +497,8 @@ mod tests {
 
     #[test]
     fn test_nil_t() {
-        let mut input = "nil t".as_bytes();
-        let mut reader = Reader::create(&mut input);
+        let input = "nil t".as_bytes();
+        let mut reader = Reader::create(input);
 
         assert_eq!(reader.read_form().unwrap().unwrap(), LispObject::nil());
         assert_eq!(reader.read_form().unwrap().unwrap(), LispObject::T);
@@ -195,10 +506,194 @@ mod tests {
 
     #[test]
     fn test_incomplete_list() {
-        let mut input = "(foo".as_bytes();
-        let mut reader = Reader::create(&mut input);
+        let input = "(foo".as_bytes();
+        let mut reader = Reader::create(input);
+        assert!(is_gen_eof(&reader.read_form()));
+
+        let cases = [
+            ("(defun f (x)", "foo.unl:1:1"),
+            ("(a\n  (b [c]", "foo.unl:2:3"),
+            ("(a \"abc", "foo.unl:1:4"),
+            ("x '", "foo.unl:1:3"),
+            ("x\n #; ", "foo.unl:2:2"),
+            ("{:a #{b", "foo.unl:1:5"),
+        ];
+
+        for (input, location) in &cases {
+            let mut reader = Reader::create_with_env(input.as_bytes(), "foo.unl", Env::new());
+            let err = loop {
+                if let Err(err) = reader.read_form() {
+                    break err;
+                }
+            };
+            assert!(err.is::<EndOfInput>(), "{}", input);
+            assert_eq!(
+                format!("{}", err),
+                format!("{}: unexpected end of input", location)
+            );
+        }
+    }
+
+    #[test]
+    fn test_quote_chars() {
+        let input = "'x `(foo ,bar ,@(baz)) ''x".as_bytes();
+        let mut reader = Reader::create(input);
+
+        let sym = |x| LispObject::Symbol(Symbol::new(x));
+        let list = |v| LispObject::List(List::from_rev_iter(v));
+
+        assert_eq!(
+            reader.read_form().unwrap().unwrap(),
+            list(vec![sym("quote"), sym("x")])
+        );
+        assert_eq!(
+            reader.read_form().unwrap().unwrap(),
+            list(vec![
+                sym("qquote"),
+                list(vec![
+                    sym("foo"),
+                    list(vec![sym("unq"), sym("bar")]),
+                    list(vec![sym("unqs"), list(vec![sym("baz")])])
+                ])
+            ])
+        );
+        assert_eq!(
+            reader.read_form().unwrap().unwrap(),
+            list(vec![sym("quote"), list(vec![sym("quote"), sym("x")])])
+        );
+    }
+
+    #[test]
+    fn test_incomplete_quote() {
+        let input = "'".as_bytes();
+        let mut reader = Reader::create(input);
+        assert!(is_gen_eof(&reader.read_form()));
+
+        let input = "(')".as_bytes();
+        let mut reader = Reader::create(input);
+        assert!(reader.read_form().is_err());
+    }
+
+    #[test]
+    fn test_datum_comments() {
+        let input = "#;x y (let (#;(a 1) (b 2)) #;#;c d e #|f|#) '#;g h #;(i) #;j".as_bytes();
+        let mut reader = Reader::create(input);
+
+        assert_eq!(reader.read_form().unwrap().unwrap(), read("y"));
+        assert_eq!(
+            reader.read_form().unwrap().unwrap(),
+            read("(let ((b 2)) e)")
+        );
+        assert_eq!(reader.read_form().unwrap().unwrap(), read("'h"));
+        assert_eq!(reader.read_form().unwrap(), None);
+
+        let input = "(a #;)".as_bytes();
+        let mut reader = Reader::create(input);
+        assert!(reader.read_form().is_err());
+
+        let input = "#;".as_bytes();
+        let mut reader = Reader::create(input);
+        assert!(is_gen_eof(&reader.read_form()));
+    }
+
+    #[test]
+    fn test_vectors() {
+        let input = "[1 [a] ()] #(x (y)) [] (a])".as_bytes();
+        let mut reader = Reader::create(input);
+        let vector = |v: Vec<LispObject>| LispObject::Vector(v.into_iter().collect());
+
+        assert_eq!(
+            reader.read_form().unwrap().unwrap(),
+            vector(vec![read("1"), vector(vec![read("a")]), LispObject::nil()])
+        );
+        assert_eq!(
+            reader.read_form().unwrap().unwrap(),
+            vector(vec![read("x"), read("(y)")])
+        );
+        assert_eq!(reader.read_form().unwrap().unwrap(), vector(vec![]));
+
+        let err = reader.read_form().unwrap_err();
+        assert!(err.downcast_ref::<SyntaxError>().is_some());
+
+        for input in &["]", "[1)", "#(1]"] {
+            let mut reader = Reader::create(input.as_bytes());
+            let err = reader.read_form().unwrap_err();
+            assert!(err.downcast_ref::<SyntaxError>().is_some(), "{}", input);
+        }
+
+        let mut reader = Reader::create("[1 2".as_bytes());
         assert!(is_gen_eof(&reader.read_form()));
     }
 
+    #[test]
+    fn test_maps() {
+        let input = "{:a 1 (b) [c] :a 2} {} {{} {}}".as_bytes();
+        let mut reader = Reader::create(input);
+        let map = |entries: Vec<(&str, &str)>| {
+            LispObject::Map(Map(entries
+                .into_iter()
+                .map(|(k, v)| (read(k), read(v)))
+                .collect()))
+        };
+
+        assert_eq!(
+            reader.read_form().unwrap().unwrap(),
+            map(vec![(":a", "2"), ("(b)", "[c]")])
+        );
+        assert_eq!(reader.read_form().unwrap().unwrap(), map(vec![]));
+        assert_eq!(
+            reader.read_form().unwrap().unwrap(),
+            map(vec![("{}", "{}")])
+        );
+
+        for input in &["}", "{:a}", "{:a 1 :b}", "{:a 1)"] {
+            let mut reader = Reader::create(input.as_bytes());
+            let err = reader.read_form().unwrap_err();
+            assert!(err.downcast_ref::<SyntaxError>().is_some(), "{}", input);
+        }
+
+        let mut reader = Reader::create("{:a 1".as_bytes());
+        assert!(is_gen_eof(&reader.read_form()));
+    }
+
+    #[test]
+    fn test_sets() {
+        let input = "#{1 (a) 1 #{}} #{}".as_bytes();
+        let mut reader = Reader::create(input);
+        let set = |items: Vec<LispObject>| LispObject::Set(Set(items.into_iter().collect()));
+
+        assert_eq!(
+            reader.read_form().unwrap().unwrap(),
+            set(vec![read("1"), read("(a)"), set(vec![])])
+        );
+        assert_eq!(reader.read_form().unwrap().unwrap(), set(vec![]));
+
+        for input in &["#{1 2)", "#{1]"] {
+            let mut reader = Reader::create(input.as_bytes());
+            let err = reader.read_form().unwrap_err();
+            assert!(err.downcast_ref::<SyntaxError>().is_some(), "{}", input);
+        }
+
+        let mut reader = Reader::create("#{1".as_bytes());
+        assert!(is_gen_eof(&reader.read_form()));
+    }
+
+    #[test]
+    fn test_undefined_dispatch_macro() {
+        for input in &["#!a", "(a #!)"] {
+            let mut reader = Reader::create(input.as_bytes());
+            let err = reader.read_form().unwrap_err();
+            let err = err.downcast_ref::<SyntaxError>().unwrap();
+            assert!(err.to_string().contains("undefined dispatch macro #!"));
+        }
+
+        let mut reader = Reader::create_with_env("#!a".as_bytes(), "<test>", Env::new());
+        assert!(reader
+            .read_form()
+            .unwrap_err()
+            .downcast_ref::<SyntaxError>()
+            .is_some());
+    }
+
     //TODO: tests on unbalanced pars
 }
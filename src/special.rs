@@ -1,13 +1,13 @@
 use crate::cons::List;
 use crate::env::Env;
 use crate::error::*;
-use crate::eval::{eval, EvalResult};
+use crate::eval::{eval, eval_body_restoring, TailCall, TailCallResult};
 use crate::object;
 use crate::object::LispObject;
 use crate::object::Symbol;
 use std::error::Error;
 
-type GenResult<T> = Result<T, Box<Error>>;
+type GenResult<T> = Result<T, Box<dyn Error>>;
 
 pub struct ParsedQuote(pub LispObject);
 
@@ -19,8 +19,16 @@ pub fn parse_quote(args: &List<LispObject>) -> GenResult<ParsedQuote> {
     Ok(ParsedQuote(args.ufirst().clone()))
 }
 
-fn quote_form(env: Env, args: List<LispObject>) -> EvalResult {
-    Ok(env.attach_st_box(parse_quote(&args))?.0)
+fn quote_form(env: Env, args: List<LispObject>) -> TailCallResult {
+    Ok(TailCall::Done(env.attach_st_box(parse_quote(&args))?.0))
+}
+
+fn check_bindable(sym: &Symbol) -> Result<(), SyntaxError> {
+    if sym.is_keyword() {
+        Err(SyntaxError::new(format!("cannot bind keyword {}", sym)))
+    } else {
+        Ok(())
+    }
 }
 
 pub struct ParsedLet<'a> {
@@ -46,6 +54,7 @@ pub fn parse_let<'a>(args: &'a List<LispObject>) -> GenResult<ParsedLet<'a>> {
             .ok_or_else(|| SyntaxError::new("empty binding clause"))?;
         let sym = object::to_symbol(sym)
             .map_err(|_e| SyntaxError::new("not a symbol in binding clause"))?;
+        check_bindable(sym)?;
 
         let val_form = binding_iter
             .next()
@@ -60,23 +69,23 @@ pub fn parse_let<'a>(args: &'a List<LispObject>) -> GenResult<ParsedLet<'a>> {
     })
 }
 
-fn let_form(env: Env, args: List<LispObject>) -> EvalResult {
+fn let_form(env: Env, args: List<LispObject>) -> TailCallResult {
     let ParsedLet { bindings, body } = env.attach_st_box(parse_let(&args))?;
 
     let mut new_env = env;
+    let mut saved = vec![];
 
     for (sym, val_form) in bindings {
-        let val = eval(new_env.clone(), val_form)?;
-        new_env.set_local_value(sym, val);
-    }
-
-    let mut res = LispObject::nil();
-
-    for form in body.iter() {
-        res = eval(new_env.clone(), &form)?;
+        match eval(new_env.clone(), val_form) {
+            Ok(val) => new_env.bind_value(sym, val, &mut saved),
+            Err(e) => {
+                new_env.restore_values(saved);
+                return Err(e);
+            }
+        }
     }
 
-    Ok(res)
+    eval_body_restoring(new_env, &body, saved)
 }
 
 pub struct ParsedLambda {
@@ -130,11 +139,12 @@ pub fn parse_lambda(args: &List<LispObject>) -> GenResult<ParsedLambda> {
     let arglist = arglist
         .iter()
         .map(|lo| {
-            object::to_symbol(lo)
-                .map(|s| s.clone())
-                .map_err(|_e| SyntaxError::new("expected symbol in arglist"))
+            let sym = object::to_symbol(lo)
+                .map_err(|_e| SyntaxError::new("expected symbol in arglist"))?;
+            check_bindable(sym)?;
+            Ok(sym.clone())
         })
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect::<Result<Vec<_>, SyntaxError>>()?;
 
     let (simple_args, restarg) = parse_arglist(arglist)?;
 
@@ -146,7 +156,7 @@ pub fn parse_lambda(args: &List<LispObject>) -> GenResult<ParsedLambda> {
     })
 }
 
-fn lambda_form(env: Env, args: List<LispObject>) -> EvalResult {
+fn lambda_form(env: Env, args: List<LispObject>) -> TailCallResult {
     let ParsedLambda {
         name,
         simple_args,
@@ -154,15 +164,18 @@ fn lambda_form(env: Env, args: List<LispObject>) -> EvalResult {
         body,
     } = env.attach_st_box(parse_lambda(&args))?;
 
-    Ok(LispObject::Fn(object::Function::new_interpreted(
+    let func = object::Function::new_interpreted(
         name,
         simple_args,
         restarg,
         body,
-    )))
+        env.capture_local_env(),
+    );
+
+    Ok(TailCall::Done(LispObject::Fn(func)))
 }
 
-fn set_fn(mut env: Env, args: List<LispObject>) -> EvalResult {
+fn set_fn(mut env: Env, args: List<LispObject>) -> TailCallResult {
     let mut args = args.iter();
     let sym = args
         .next()
@@ -176,10 +189,10 @@ fn set_fn(mut env: Env, args: List<LispObject>) -> EvalResult {
     let func = env.attach_st(object::to_function_owned(eval(env.clone(), &func)?))?;
 
     env.set_global_function(sym.clone(), func);
-    Ok(LispObject::nil())
+    Ok(TailCall::Done(LispObject::nil()))
 }
 
-fn set_macro_fn(mut env: Env, args: List<LispObject>) -> EvalResult {
+fn set_macro_fn(mut env: Env, args: List<LispObject>) -> TailCallResult {
     let mut args = args.iter();
     let sym = args
         .next()
@@ -193,10 +206,84 @@ fn set_macro_fn(mut env: Env, args: List<LispObject>) -> EvalResult {
     let func = env.attach_st(object::to_function_owned(eval(env.clone(), &func)?))?;
 
     env.set_global_macro(sym.clone(), func);
-    Ok(LispObject::nil())
+    Ok(TailCall::Done(LispObject::nil()))
+}
+
+fn parse_var_symbol<'a>(
+    env: &Env,
+    form_name: &str,
+    sym: Option<&'a LispObject>,
+) -> Result<&'a Symbol, ErrorWithStackTrace> {
+    let sym =
+        sym.ok_or_else(|| env.st_err(SyntaxError::new(format!("no symbol in {}", form_name))))?;
+    let sym = object::to_symbol(sym)
+        .map_err(|_| env.st_err(SyntaxError::new(format!("not a symbol in {}", form_name))))?;
+    env.attach_st(check_bindable(sym))?;
+    Ok(sym)
+}
+
+fn defvar_form(mut env: Env, args: List<LispObject>) -> TailCallResult {
+    let mut args = args.iter();
+    let sym = parse_var_symbol(&env, "defvar", args.next())?;
+    let val_form = args.next();
+
+    if args.next().is_some() {
+        Err(env.st_err(SyntaxError::new("too many arguments in defvar")))?
+    }
+
+    env.declare_special_var(sym.clone());
+
+    if let Some(val_form) = val_form {
+        if env.lookup_global_value(sym).is_none() {
+            let val = eval(env.clone(), val_form)?;
+            env.set_global_value(sym.clone(), val);
+        }
+    }
+
+    Ok(TailCall::Done(LispObject::nil()))
+}
+
+fn defparameter_form(mut env: Env, args: List<LispObject>) -> TailCallResult {
+    let mut args = args.iter();
+    let sym = parse_var_symbol(&env, "defparameter", args.next())?;
+    let val_form = args
+        .next()
+        .ok_or_else(|| env.st_err(SyntaxError::new("no value in defparameter")))?;
+
+    if args.next().is_some() {
+        Err(env.st_err(SyntaxError::new("too many arguments in defparameter")))?
+    }
+
+    let val = eval(env.clone(), val_form)?;
+    env.declare_special_var(sym.clone());
+    env.set_global_value(sym.clone(), val);
+
+    Ok(TailCall::Done(LispObject::nil()))
 }
 
-fn if_form(env: Env, args: List<LispObject>) -> EvalResult {
+fn setq_form(mut env: Env, args: List<LispObject>) -> TailCallResult {
+    if !args.len().is_multiple_of(2) {
+        Err(env.st_err(SyntaxError::new("odd number of arguments in setq")))?
+    }
+
+    let mut args = args.iter();
+    let mut res = LispObject::nil();
+
+    while let Some(sym) = args.next() {
+        let sym = parse_var_symbol(&env, "setq", Some(sym))?;
+        let val = eval(env.clone(), args.next().unwrap())?;
+
+        if !env.assign_value(sym, val.clone()) {
+            Err(env.st_err(UndefinedSymbol::new(sym.name(), false)))?
+        }
+
+        res = val;
+    }
+
+    Ok(TailCall::Done(res))
+}
+
+fn if_form(env: Env, args: List<LispObject>) -> TailCallResult {
     let mut args = args.iter();
     let cond = args
         .next()
@@ -209,9 +296,9 @@ fn if_form(env: Env, args: List<LispObject>) -> EvalResult {
 
     let cond = eval(env.clone(), cond)?;
     if cond == nil {
-        eval(env, else_form)
+        Ok(TailCall::Eval(env, else_form.clone()))
     } else {
-        eval(env, then_form)
+        Ok(TailCall::Eval(env, then_form.clone()))
     }
 }
 
@@ -226,10 +313,16 @@ pub fn prepare_specials(env: &mut Env) {
     set("set-fn", set_fn);
     set("set-macro-fn", set_macro_fn);
     set("lambda", lambda_form);
+    set("defvar", defvar_form);
+    set("defparameter", defparameter_form);
+    set("setq", setq_form);
 }
 
 #[cfg(test)]
 mod tests {
+    use super::prepare_specials;
+    use crate::common;
+    use crate::env::Env;
     use crate::error;
     use crate::object;
     use crate::test_utils::*;
@@ -275,6 +368,50 @@ mod tests {
         assert!(object::to_function(&ctx.ok_eval("(lambda foo (x) x)")).is_ok());
     }
 
+    #[test]
+    fn test_keywords() {
+        let ctx = ctx();
+        assert_err!(ctx, "(let ((:x 1)) :x)", error::SyntaxError);
+        assert_err!(ctx, "(lambda (:x) :x)", error::SyntaxError);
+        assert_err!(ctx, "(lambda (& :rest) :rest)", error::SyntaxError);
+        assert_err!(ctx, "(defvar :x 1)", error::SyntaxError);
+        assert_err!(ctx, "(defparameter :x 1)", error::SyntaxError);
+        assert_err!(ctx, "(setq :x 1)", error::SyntaxError);
+
+        assert_ok!(ctx, ":foo", ":foo");
+        assert_ok!(ctx, "(if :foo :then :else)", ":then");
+        assert_ok!(ctx, "(let ((x :foo)) x)", ":foo");
+        assert_ok!(ctx, "(quote (:a 1 :b 2))", "(:a 1 :b 2)");
+    }
+
+    #[test]
+    fn test_lambda_closures() {
+        let ctx = ctx();
+
+        assert_ok!(
+            ctx,
+            "(let ((f (let ((x 1)) (lambda () x)))) (let ((g f)) (set-fn g g) (g)))",
+            "1"
+        );
+        assert_ok!(
+            ctx,
+            "(set-fn const (lambda (n) (lambda (x) n)))
+             (set-fn one (const 1))
+             (one 2)",
+            "1"
+        );
+        assert_err!(
+            ctx,
+            "(set-fn get-x (lambda () x)) (let ((x 1)) (get-x))",
+            error::UndefinedSymbol
+        );
+        assert_ok!(
+            ctx,
+            "(set-fn get-x (let ((x 1)) (lambda () x))) (let ((x 2)) (get-x))",
+            "1"
+        );
+    }
+
     #[test]
     fn test_set_fn() {
         let ctx = ctx();
@@ -336,4 +473,120 @@ mod tests {
             "2"
         );
     }
+
+    #[test]
+    fn test_defvar() {
+        let ctx = ctx();
+        assert_err!(ctx, "(defvar)", error::SyntaxError);
+        assert_err!(ctx, "(defvar 1)", error::SyntaxError);
+        assert_err!(ctx, "(defvar x 1 2)", error::SyntaxError);
+        assert_err!(ctx, "(defvar x) x", error::UndefinedSymbol);
+
+        assert_ok!(ctx, "(defvar x 1)", "nil");
+        assert_ok!(ctx, "(defvar x 1) x", "1");
+        assert_ok!(ctx, "(defvar x 1) (defvar x 2) x", "1");
+        assert_ok!(ctx, "(defvar x (quote (1 2))) x", "(1 2)");
+    }
+
+    #[test]
+    fn test_defparameter() {
+        let ctx = ctx();
+        assert_err!(ctx, "(defparameter)", error::SyntaxError);
+        assert_err!(ctx, "(defparameter x)", error::SyntaxError);
+        assert_err!(ctx, "(defparameter 1 1)", error::SyntaxError);
+        assert_err!(ctx, "(defparameter x 1 2)", error::SyntaxError);
+
+        assert_ok!(ctx, "(defparameter x 1) x", "1");
+        assert_ok!(ctx, "(defparameter x 1) (defparameter x 2) x", "2");
+        assert_ok!(ctx, "(defvar x 1) (defparameter x 2) x", "2");
+    }
+
+    #[test]
+    fn test_setq() {
+        let ctx = ctx();
+        assert_err!(ctx, "(setq x)", error::SyntaxError);
+        assert_err!(ctx, "(setq 1 2)", error::SyntaxError);
+        assert_err!(ctx, "(setq x 1)", error::UndefinedSymbol);
+
+        assert_ok!(ctx, "(setq)", "nil");
+        assert_ok!(ctx, "(defvar x 1) (setq x 2)", "2");
+        assert_ok!(ctx, "(defvar x 1) (setq x 2) x", "2");
+        assert_ok!(ctx, "(let ((x 1)) (setq x 2) x)", "2");
+        assert_ok!(ctx, "(let ((x 1) (y 2)) (setq x 3 y x) y)", "3");
+        assert_ok!(ctx, "(defvar x 1) (let ((x 2)) (setq x 3)) x", "1");
+        assert_ok!(ctx, "(let ((x 1)) (let ((x 2)) (setq x 3)) x)", "1");
+        assert_ok!(ctx, "(let ((x 1)) (let ((y 2)) (setq x 3)) x)", "3");
+        assert_ok!(
+            ctx,
+            "(defvar x 1) (set-fn f (lambda () (setq x (quote foo)))) (f) x",
+            "foo"
+        );
+        assert_ok!(
+            ctx,
+            "(let ((x 1)) (set-fn get-x (lambda () x)) (setq x 2) (get-x))",
+            "2"
+        );
+    }
+
+    #[test]
+    fn test_special_vars() {
+        let ctx = ctx();
+
+        assert_ok!(
+            ctx,
+            "(defvar x 1) (set-fn get-x (lambda () x)) (let ((x 2)) (get-x))",
+            "2"
+        );
+        assert_ok!(
+            ctx,
+            "(defvar x 1) (set-fn get-x (lambda () x)) (let ((x 2)) (get-x)) (get-x)",
+            "1"
+        );
+        assert_ok!(
+            ctx,
+            "(defparameter x 1) (set-fn get-x (lambda () x)) (set-fn f (lambda (x) (get-x))) (f 3)",
+            "3"
+        );
+        assert_ok!(ctx, "(defvar x 1) (set-fn f (lambda (x) x)) (f 3) x", "1");
+        assert_ok!(
+            ctx,
+            "(defvar x 1) (set-fn f (let ((x 2)) (lambda () x))) (f)",
+            "1"
+        );
+        assert_ok!(ctx, "(defvar x 1) (let ((x 2)) (setq x 3)) x", "1");
+        assert_ok!(
+            ctx,
+            "(defvar x 1) (set-fn get-x (lambda () x)) (let ((x 2)) (setq x 3) (get-x))",
+            "3"
+        );
+        assert_ok!(
+            ctx,
+            "(defvar y) (set-fn get-y (lambda () y)) (let ((y 5)) (get-y))",
+            "5"
+        );
+        assert_err!(
+            ctx,
+            "(defvar y) (set-fn get-y (lambda () y)) (let ((y 5)) (get-y)) y",
+            error::UndefinedSymbol
+        );
+    }
+
+    #[test]
+    fn test_special_vars_restored_on_error() {
+        let mut env = Env::new();
+        prepare_specials(&mut env);
+        let eval_str = |s| common::macroexpand_and_eval(env.clone(), &read(s));
+
+        assert!(eval_str("(defvar x 1)").is_ok());
+        assert!(eval_str("(set-fn f (lambda (x) (undefined-fn)))").is_ok());
+
+        assert!(eval_str("(let ((x 2)) (undefined-fn))").is_err());
+        assert_eq!(eval_str("x").unwrap(), read("1"));
+
+        assert!(eval_str("(let ((x 2) (y (undefined-fn))) x)").is_err());
+        assert_eq!(eval_str("x").unwrap(), read("1"));
+
+        assert!(eval_str("(f 2)").is_err());
+        assert_eq!(eval_str("x").unwrap(), read("1"));
+    }
 }
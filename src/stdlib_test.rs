@@ -20,6 +20,9 @@ fn test_quasiquote() {
         "(let ((x (list 1 2 3))) (qquote ((unqs x))))",
         "(1 2 3)"
     );
+
+    assert_ok!(ctx, "(let ((x 1) (y '(2 3))) `(a ,x ,@y))", "(a 1 2 3)");
+    assert_ok!(ctx, "`(a ,@'(b c) d)", "(a b c d)");
 }
 
 #[test]
@@ -47,3 +50,172 @@ fn test_nested_quasiquote() {
         "6"
     );
 }
+
+#[test]
+fn test_closures() {
+    let ctx = ctx();
+
+    assert_ok!(
+        ctx,
+        "(defun adder (n) (lambda (x) (+ x n)))
+         (mapcar (adder 10) (list 1 2 3))",
+        "(11 12 13)"
+    );
+    assert_ok!(
+        ctx,
+        "(let ((n 5)) (mapcar (lambda (x) (* x n)) (range 3)))",
+        "(0 5 10 15)"
+    );
+    assert_ok!(
+        ctx,
+        "(defun adder (n) (lambda (x) (+ x n)))
+         (defun compose (f g) (lambda (x) (funcall f (funcall g x))))
+         (funcall (compose (adder 1) (adder 2)) 3)",
+        "6"
+    );
+}
+
+#[test]
+fn test_self_referential_closures() {
+    let ctx = ctx();
+    let mk = "(defun mk ()
+                (let ((f nil))
+                  (setq f (lambda () f))
+                  f))";
+
+    for (expr, expected) in &[
+        ("(equal (mk) (mk))", "nil"),
+        ("(let ((f (mk))) (equal f (funcall f)))", "t"),
+        ("(let ((f (mk))) (contains? (hash-set f) f))", "t"),
+        ("(get (hash-map (mk) 1) (mk))", "nil"),
+    ] {
+        assert_ok!(ctx, format!("{} {}", mk, expr), *expected);
+    }
+
+    let f = ctx.ok_eval(format!("{} (mk)", mk));
+    assert!(format!("{:?}", f).starts_with("Fn(Function("));
+}
+
+#[test]
+fn test_tail_calls() {
+    let ctx = ctx();
+
+    assert_ok!(
+        ctx,
+        "(defun count-down (n)
+           (cond ((equal n 0) (quote done))
+                 (t (let ((m (- n 1)))
+                      (count-down m)))))
+         (count-down 20000)",
+        "done"
+    );
+    assert_ok!(
+        ctx,
+        "(defun loop (f n acc)
+           (if (equal n 0)
+               acc
+               (funcall f f (- n 1) (+ acc 1))))
+         (loop (symf (quote loop)) 20000 0)",
+        "20000"
+    );
+    assert_ok!(
+        ctx,
+        "(reduce (symf (quote +)) 0 (range 20000))",
+        "200010000"
+    );
+    assert_ok!(ctx, "(first (reverse (range 20000)))", "20000");
+    assert_ok!(ctx, "(range 0)", "(0)");
+    assert_err!(ctx, "(range-inner 1 nil)", crate::error::UndefinedSymbol);
+}
+
+#[test]
+fn test_shared_state() {
+    let ctx = ctx();
+
+    assert_ok!(
+        ctx,
+        "(defun make-counter ()
+           (let ((n 0))
+             (lambda () (setq n (+ n 1)))))
+         (defvar counter (make-counter))
+         (funcall counter)
+         (funcall counter)
+         (list (funcall counter) (funcall (make-counter)))",
+        "(3 1)"
+    );
+    assert_ok!(
+        ctx,
+        "(defvar total 0)
+         (mapcar (lambda (x) (setq total (+ total x))) (list 1 2 3))
+         total",
+        "6"
+    );
+    assert_ok!(
+        ctx,
+        "(let ((b (make-box 0)))
+           (let ((inc (lambda () (box-swap! b (symf (quote +)) 1)))
+                 (get (lambda () (box-get b))))
+             (funcall inc)
+             (funcall inc)
+             (funcall get)))",
+        "2"
+    );
+}
+
+#[test]
+fn test_error_locations() {
+    let ctx = ctx();
+
+    let err = ctx.err_eval(
+        "(defun f (x)
+  (+ x \"a\")
+  nil)
+(defun g ()
+  (when t
+    (f 1))
+  nil)
+(g)",
+    );
+    assert_eq!(
+        format!("{}", err),
+        "<test>:2:3: cannot cast \"a\" to number"
+    );
+
+    let frames = err
+        .stack_trace
+        .iter()
+        .map(|frame| frame.location.as_ref().map(|l| format!("{}", l)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        frames,
+        vec![
+            Some("<test>:2:3".to_string()),
+            Some("<test>:6:5".to_string()),
+            Some("<test>:8:1".to_string()),
+            None,
+        ]
+    );
+
+    let err = ctx.err_eval("(list 1\n      (let 5))");
+    assert_eq!(
+        format!("{}", err),
+        "<test>:2:7: let bindings are not a list"
+    );
+
+    let err = ctx.err_eval("1\n  foo");
+    assert_eq!(format!("{}", err), "<test>:2:3: undefined symbol foo");
+
+    let err = ctx.err_eval("(list 1\n      (let ((x '(foo))) (list x foo)))");
+    assert_eq!(format!("{}", err), "<test>:2:33: undefined symbol foo");
+
+    let err = ctx.err_eval("(let ((y 1)\n      (x foo))\n  x)");
+    assert_eq!(format!("{}", err), "<test>:2:10: undefined symbol foo");
+
+    let err = ctx.err_eval(
+        "(defun f ()
+  (if t
+      (list foo)))
+(f)",
+    );
+    assert_eq!(format!("{}", err), "<test>:3:13: undefined symbol foo");
+}
@@ -0,0 +1,75 @@
+use crate::lexer::Token;
+use crate::location::SourceLocation;
+use std::cell::RefCell;
+use std::cell::RefMut;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::io;
+use std::rc::Rc;
+
+type GenResult<T> = Result<T, Box<dyn Error>>;
+
+/// Input of reader, either tokens or single chars can be read from it.
+pub trait Input {
+    /// Reads next token, returns `None` at the end of input.
+    fn next_token(&mut self) -> GenResult<Option<Token>>;
+
+    /// Location of the first char of the last read token.
+    fn token_location(&self) -> SourceLocation;
+
+    /// Skips the rest of current line, see `Lexer::skip_rest_of_line`.
+    fn skip_rest_of_line(&mut self) -> io::Result<()>;
+
+    /// Number of chars consumed so far.
+    fn offset(&self) -> usize;
+
+    /// Reads next char, returns `None` at the end of input.
+    fn read_char(&mut self) -> GenResult<Option<char>>;
+
+    /// Returns next char without consuming it, `None` at the end of input.
+    fn peek_char(&mut self) -> GenResult<Option<char>>;
+}
+
+/// Input shared between reader and Lisp code, e.g. reader macros.
+/// Clones read from the same input, equality is identity.
+#[derive(Clone)]
+pub struct Stream(Rc<RefCell<dyn Input>>);
+
+impl Stream {
+    pub fn new(input: impl Input + 'static) -> Self {
+        Stream(Rc::new(RefCell::new(input)))
+    }
+
+    pub fn input(&self) -> RefMut<'_, dyn Input> {
+        self.0.borrow_mut()
+    }
+
+    fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as *const u8 as usize
+    }
+}
+
+impl fmt::Debug for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Stream(0x{:x})", self.id())
+    }
+}
+
+impl PartialEq for Stream {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.id() == rhs.id()
+    }
+}
+
+impl Eq for Stream {}
+
+impl Hash for Stream {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        state.write_usize(self.id());
+    }
+}
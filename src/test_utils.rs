@@ -33,7 +33,7 @@ impl Context {
                 panic!("cannot load stdlib without specials or natives");
             }
 
-            common::eval_stdlib(&env);
+            common::eval_stdlib(&env).expect("error during stdlib eval");
         }
 
         Self { env: env }
@@ -45,14 +45,15 @@ impl Context {
 
     pub fn eval(&self, s: impl Into<String>) -> EvalResult {
         let env = self.env();
-        let s = s.into();
-        let mut bytes = s.as_bytes();
-        let mut reader = Reader::create(&mut bytes);
+        let input = io::Cursor::new(s.into());
+        let mut reader = Reader::create_with_env(input, "<test>", env.clone());
         let mut res = Ok(LispObject::nil());
         loop {
             match reader.read_form() {
                 Ok(Some(form)) => {
-                    res = common::macroexpand_and_eval(env.clone(), &form);
+                    let mut env = env.clone();
+                    env.set_location(reader.form_location());
+                    res = common::macroexpand_and_eval(env, &form);
                 }
                 Ok(None) => break,
                 Err(e) => panic!("reader error in Context::eval: {}", e),
@@ -71,18 +72,13 @@ impl Context {
 }
 
 pub fn read(s: impl Into<String>) -> LispObject {
-    let s = s.into();
-    let mut bytes = s.as_bytes();
-    let mut reader = Reader::create(&mut bytes);
+    let mut reader = Reader::create(io::Cursor::new(s.into()));
     reader.read_form().unwrap().unwrap()
 }
 
-pub fn is_gen_eof<T>(result: &Result<T, Box<Error>>) -> bool {
+pub fn is_gen_eof<T>(result: &Result<T, Box<dyn Error>>) -> bool {
     match result {
-        Err(e) => match e.downcast_ref::<io::Error>() {
-            Some(io_err) => io_err.kind() == io::ErrorKind::UnexpectedEof,
-            None => false,
-        },
+        Err(e) => e.is::<error::EndOfInput>(),
         _ => false,
     }
 }